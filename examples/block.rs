@@ -1,5 +1,5 @@
 use std::io;
-use sysfs_class::{Block, SysClass};
+use sysfs_class::{Block, BlockDeviceType, SysClass};
 
 fn main() -> io::Result<()> {
     for block in Block::all()? {
@@ -9,6 +9,20 @@ fn main() -> io::Result<()> {
             println!("  Vendor: {:?}", block.device_vendor());
             println!("  Rev: {:?}", block.device_rev());
             println!("  Children: {:#?}", block.children());
+        } else if block.device_type() == BlockDeviceType::Partition {
+            println!("Partition: {}", block.id());
+            println!("  Number: {:?}", block.partn());
+            println!("  Name: {:?}", block.partname());
+            println!("  UUID: {:?}", block.partuuid());
+            println!("  Scheme: {:?}", block.partition_table_scheme());
+            if let (Ok(start), Ok(size)) = (block.start_bytes(), block.size_bytes()) {
+                println!("  Start: {} ({} bytes)", start, start.as_u64());
+                println!("  Size: {} ({} bytes)", size, size.as_u64());
+            }
+            println!(
+                "  Aligned: {:?}",
+                block.partition_alignment().map(|a| a.is_aligned())
+            );
         }
     }
 
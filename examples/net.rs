@@ -18,6 +18,16 @@ fn main() -> io::Result<()> {
             "    TX: {} MiB",
             statistics.tx_bytes().unwrap() / (1024 * 1024)
         );
+
+        let counters = statistics.counters()?;
+        println!(
+            "    Errors: {} RX, {} TX",
+            counters.rx_errors, counters.tx_errors
+        );
+        println!(
+            "    Dropped: {} RX, {} TX",
+            counters.rx_dropped, counters.tx_dropped
+        );
     }
 
     Ok(())
@@ -0,0 +1,23 @@
+use std::io;
+use sysfs_class::{PciDevice, PciIds, SysClass};
+
+fn main() -> io::Result<()> {
+    let ids = PciIds::load();
+
+    for dev in PciDevice::all()? {
+        let names = dev.names(&ids)?;
+        println!(
+            "{} {}: {} {}",
+            dev.id(),
+            names.subclass.or(names.class).unwrap_or_default(),
+            names
+                .vendor
+                .unwrap_or_else(|| format!("{:04x}", dev.vendor().unwrap_or(0))),
+            names
+                .device
+                .unwrap_or_else(|| format!("{:04x}", dev.device().unwrap_or(0))),
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,7 @@
+use std::io;
+use sysfs_class::PciTopology;
+
+fn main() -> io::Result<()> {
+    print!("{}", PciTopology::new()?);
+    Ok(())
+}
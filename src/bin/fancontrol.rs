@@ -0,0 +1,166 @@
+//! Drives hwmon PWM outputs from temperature inputs, following the fan curves of a TOML
+//! config. The outputs are handed back to their previous control method on exit.
+//!
+//! ```toml
+//! interval_ms = 1000
+//!
+//! [[fan]]
+//! hwmon = "nct6775"
+//! pwm = 2
+//! temps = [{ hwmon = "k10temp", temp = 1 }]
+//! curve = [[40.0, 60], [70.0, 255]]
+//! hysteresis = 3.0
+//! min_duty = 60
+//! spin_up_duty = 255
+//! spin_up_ms = 1000
+//! smoothing = 0.5
+//! ```
+
+use serde::Deserialize;
+use std::io::{Error, ErrorKind, Result};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use std::{env, fs, process, thread};
+use sysfs_class::{
+    FanControlOptions, FanController, FanCurve, HwMon, HwMonPwm, HwMonStableId, HwMonTemp, SysClass,
+};
+
+static RUNNING: AtomicBool = AtomicBool::new(true);
+
+extern "C" fn stop(_signal: libc::c_int) {
+    RUNNING.store(false, Ordering::SeqCst);
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    #[serde(default = "default_interval_ms")]
+    interval_ms: u64,
+    fan: Vec<FanConfig>,
+}
+
+fn default_interval_ms() -> u64 {
+    1000
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FanConfig {
+    hwmon: String,
+    pwm: u64,
+    temps: Vec<TempConfig>,
+    curve: Vec<(f64, u8)>,
+    #[serde(default)]
+    hysteresis: f64,
+    #[serde(default)]
+    min_duty: u8,
+    #[serde(default)]
+    spin_up_duty: u8,
+    #[serde(default)]
+    spin_up_ms: u64,
+    #[serde(default = "default_smoothing")]
+    smoothing: f64,
+}
+
+fn default_smoothing() -> f64 {
+    1.0
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TempConfig {
+    hwmon: String,
+    temp: u64,
+}
+
+/// Finds a hwmon device by its stable id, such as `nct6775:nct6775@platform/nct6775.656`
+/// or just `nct6775`, which must match exactly one device. An id such as `hwmon2` names
+/// the device directly, though that number may change across reboots.
+fn find_hwmon(name: &str) -> Result<HwMon> {
+    let is_sysfs_id = name
+        .strip_prefix("hwmon")
+        .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()));
+    if is_sysfs_id {
+        return HwMon::new(name);
+    }
+
+    HwMon::find_stable(&name.parse::<HwMonStableId>()?)
+}
+
+fn run(path: &str) -> Result<()> {
+    let config = fs::read_to_string(path)?;
+    let config: Config = toml::from_str(&config)
+        .map_err(|err| Error::new(ErrorKind::InvalidData, format!("{}: {}", path, err)))?;
+
+    // Stopping by signal lets the outputs be handed back before exiting.
+    let handler = stop as extern "C" fn(libc::c_int);
+    unsafe {
+        libc::signal(libc::SIGINT, handler as libc::sighandler_t);
+        libc::signal(libc::SIGTERM, handler as libc::sighandler_t);
+    }
+
+    let mut sources = Vec::new();
+    for fan in &config.fan {
+        let pwm = find_hwmon(&fan.hwmon)?;
+        let temps = fan
+            .temps
+            .iter()
+            .map(|temp| Ok((find_hwmon(&temp.hwmon)?, temp.temp)))
+            .collect::<Result<Vec<_>>>()?;
+        sources.push((pwm, temps));
+    }
+
+    let mut controllers = Vec::new();
+    for (fan, (pwm, temps)) in config.fan.iter().zip(&sources) {
+        let temps = temps
+            .iter()
+            .map(|(hwmon, id)| HwMonTemp::new(hwmon, *id))
+            .collect::<Result<Vec<_>>>()?;
+
+        let options = FanControlOptions {
+            hysteresis: fan.hysteresis,
+            min_duty: fan.min_duty,
+            spin_up_duty: fan.spin_up_duty,
+            spin_up_time: Duration::from_millis(fan.spin_up_ms),
+            smoothing: fan.smoothing,
+        };
+
+        // The controller rejects options which are out of range, before taking the output.
+        let controller = FanCurve::new(fan.curve.clone()).and_then(|curve| {
+            FanController::new(&HwMonPwm::new(pwm, fan.pwm)?, &temps, curve, options)
+        });
+        controllers.push(controller.map_err(|why| {
+            Error::new(why.kind(), format!("{} pwm{}: {}", fan.hwmon, fan.pwm, why))
+        })?);
+    }
+
+    let interval = Duration::from_millis(config.interval_ms);
+    while RUNNING.load(Ordering::SeqCst) {
+        for controller in &mut controllers {
+            controller.update()?;
+        }
+
+        thread::sleep(interval);
+    }
+
+    for controller in controllers {
+        controller.restore()?;
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let path = match env::args().nth(1) {
+        Some(path) => path,
+        None => {
+            eprintln!("usage: fancontrol <config.toml>");
+            process::exit(2);
+        }
+    };
+
+    if let Err(why) = run(&path) {
+        eprintln!("fancontrol: {}", why);
+        process::exit(1);
+    }
+}
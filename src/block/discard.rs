@@ -0,0 +1,184 @@
+use crate::{Block, SysClass};
+use std::io::{Error, ErrorKind, Result};
+
+/// Whether a block device accepts discard (TRIM / UNMAP) requests.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DiscardSupport {
+    /// The hardware does not accept discards.
+    Unsupported,
+    /// The hardware accepts discards, but they were disabled by setting
+    /// `queue/discard_max_bytes` to zero.
+    Disabled {
+        /// Largest single discard request which the hardware accepts, in bytes.
+        max_hw_bytes: u64,
+    },
+    Supported {
+        /// Smallest unit of discard, in bytes.
+        granularity: u64,
+        /// Largest single discard request, in bytes.
+        max_bytes: u64,
+        /// Largest single discard request which the hardware accepts, in bytes.
+        max_hw_bytes: u64,
+        /// The device can also zero ranges without writing data to them.
+        write_zeroes: bool,
+    },
+}
+
+impl DiscardSupport {
+    pub fn is_supported(&self) -> bool {
+        matches!(self, DiscardSupport::Supported { .. })
+    }
+
+    fn max_hw_bytes(&self) -> u64 {
+        match *self {
+            DiscardSupport::Unsupported => 0,
+            DiscardSupport::Disabled { max_hw_bytes }
+            | DiscardSupport::Supported { max_hw_bytes, .. } => max_hw_bytes,
+        }
+    }
+
+    /// Combines the support of two stacked layers, where discards must pass through both.
+    fn and(self, other: DiscardSupport) -> DiscardSupport {
+        match (self, other) {
+            (DiscardSupport::Unsupported, _) | (_, DiscardSupport::Unsupported) => {
+                DiscardSupport::Unsupported
+            }
+            (
+                DiscardSupport::Supported {
+                    granularity: a_granularity,
+                    max_bytes: a_max_bytes,
+                    max_hw_bytes: a_max_hw_bytes,
+                    write_zeroes: a_write_zeroes,
+                },
+                DiscardSupport::Supported {
+                    granularity: b_granularity,
+                    max_bytes: b_max_bytes,
+                    max_hw_bytes: b_max_hw_bytes,
+                    write_zeroes: b_write_zeroes,
+                },
+            ) => DiscardSupport::Supported {
+                granularity: a_granularity.max(b_granularity),
+                max_bytes: a_max_bytes.min(b_max_bytes),
+                max_hw_bytes: a_max_hw_bytes.min(b_max_hw_bytes),
+                write_zeroes: a_write_zeroes && b_write_zeroes,
+            },
+            (a, b) => DiscardSupport::Disabled {
+                max_hw_bytes: a.max_hw_bytes().min(b.max_hw_bytes()),
+            },
+        }
+    }
+}
+
+impl Block {
+    /// The provisioning mode of the SCSI disk behind this device, such as `unmap` or `full`.
+    pub fn device_provisioning_mode(&self) -> Result<String> {
+        let disk = self.whole_disk()?;
+        let mut scsi_disks = disk.path().join("device/scsi_disk").read_dir()?;
+        let scsi_disk = match scsi_disks.next() {
+            Some(entry) => entry?.path(),
+            None => return Err(std::io::ErrorKind::NotFound.into()),
+        };
+
+        disk.trim_file(scsi_disk.join("provisioning_mode"))
+    }
+
+    /// Discard support as advertised by the request queue of this device alone.
+    pub fn discard_support(&self) -> Result<DiscardSupport> {
+        let disk = self.whole_disk()?;
+
+        let max_hw_bytes = disk.queue_discard_max_hw_bytes()?;
+        if max_hw_bytes == 0 {
+            return Ok(DiscardSupport::Unsupported);
+        }
+
+        // SCSI disks without thin provisioning may still report queue limits.
+        if let Ok(mode) = disk.device_provisioning_mode() {
+            if mode == "full" || mode == "disabled" {
+                return Ok(DiscardSupport::Unsupported);
+            }
+        }
+
+        let max_bytes = disk.queue_discard_max_bytes()?;
+        if max_bytes == 0 {
+            return Ok(DiscardSupport::Disabled { max_hw_bytes });
+        }
+
+        Ok(DiscardSupport::Supported {
+            granularity: disk.queue_discard_granularity()?,
+            max_bytes,
+            max_hw_bytes,
+            write_zeroes: disk.queue_write_zeroes_max_bytes().unwrap_or(0) > 0,
+        })
+    }
+
+    /// Discard support all the way down to the physical media, following the slaves of
+    /// device mapper and multiple device layers.
+    ///
+    /// Discards only reach the media if every layer, and every device underneath it,
+    /// supports them. If a layer only has them disabled, the result is `Disabled` as long
+    /// as the hardware of every layer supports them.
+    pub fn discard_support_physical(&self) -> Result<DiscardSupport> {
+        let mut support = self.discard_support()?;
+        if support == DiscardSupport::Unsupported {
+            return Ok(support);
+        }
+
+        if let Some(slaves) = self.whole_disk()?.slaves() {
+            for slave in slaves? {
+                let slave = slave?;
+                let name = slave
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .ok_or_else(|| {
+                        Error::new(
+                            ErrorKind::InvalidData,
+                            format!("invalid slave name: {}", slave.display()),
+                        )
+                    })?;
+                support = support.and(Block::new(name)?.discard_support_physical()?);
+                if support == DiscardSupport::Unsupported {
+                    break;
+                }
+            }
+        }
+
+        Ok(support)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn supported(granularity: u64, max_bytes: u64, max_hw_bytes: u64) -> DiscardSupport {
+        DiscardSupport::Supported {
+            granularity,
+            max_bytes,
+            max_hw_bytes,
+            write_zeroes: true,
+        }
+    }
+
+    #[test]
+    fn stacked_support() {
+        assert_eq!(
+            supported(512, 1 << 30, 1 << 31).and(supported(4096, 1 << 20, 1 << 32)),
+            supported(4096, 1 << 20, 1 << 31)
+        );
+
+        let disabled = DiscardSupport::Disabled {
+            max_hw_bytes: 1 << 20,
+        };
+        assert_eq!(supported(512, 1 << 30, 1 << 31).and(disabled), disabled);
+        assert_eq!(disabled.and(supported(512, 1 << 30, 1 << 31)), disabled);
+        assert_eq!(
+            disabled.and(DiscardSupport::Unsupported),
+            DiscardSupport::Unsupported
+        );
+        assert_eq!(
+            supported(512, 1 << 30, 1 << 31).and(DiscardSupport::Unsupported),
+            DiscardSupport::Unsupported
+        );
+        assert!(!disabled.is_supported());
+    }
+}
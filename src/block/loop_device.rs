@@ -0,0 +1,361 @@
+use crate::{Block, SysClass};
+use std::fs::{File, OpenOptions};
+use std::io::{Error, ErrorKind, Result};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+
+// Loop ioctls and flags. Copied from kernel header.
+// https://git.kernel.org/pub/scm/linux/kernel/git/torvalds/linux.git/tree/include/uapi/linux/loop.h
+const LOOP_SET_FD: libc::c_ulong = 0x4C00;
+const LOOP_CLR_FD: libc::c_ulong = 0x4C01;
+const LOOP_SET_STATUS64: libc::c_ulong = 0x4C04;
+const LOOP_SET_DIRECT_IO: libc::c_ulong = 0x4C08;
+const LOOP_CTL_ADD: libc::c_ulong = 0x4C80;
+const LOOP_CTL_REMOVE: libc::c_ulong = 0x4C81;
+const LOOP_CTL_GET_FREE: libc::c_ulong = 0x4C82;
+
+const LO_FLAGS_READ_ONLY: u32 = 1;
+const LO_FLAGS_AUTOCLEAR: u32 = 4;
+const LO_FLAGS_PARTSCAN: u32 = 8;
+
+const LO_NAME_SIZE: usize = 64;
+const LO_KEY_SIZE: usize = 32;
+
+const LOOP_CONTROL: &str = "/dev/loop-control";
+
+/// Number of times a free device is looked up again when another process claims it first.
+const ATTACH_RETRIES: usize = 16;
+
+#[repr(C)]
+struct LoopInfo64 {
+    lo_device: u64,
+    lo_inode: u64,
+    lo_rdevice: u64,
+    lo_offset: u64,
+    lo_sizelimit: u64,
+    lo_number: u32,
+    lo_encrypt_type: u32,
+    lo_encrypt_key_size: u32,
+    lo_flags: u32,
+    lo_file_name: [u8; LO_NAME_SIZE],
+    lo_crypt_name: [u8; LO_NAME_SIZE],
+    lo_encrypt_key: [u8; LO_KEY_SIZE],
+    lo_init: [u64; 2],
+}
+
+impl LoopInfo64 {
+    fn new(number: u32, backing_file: &Path, options: &LoopOptions) -> Self {
+        let mut flags = 0;
+        if options.read_only {
+            flags |= LO_FLAGS_READ_ONLY;
+        }
+        if options.autoclear {
+            flags |= LO_FLAGS_AUTOCLEAR;
+        }
+        if options.partscan {
+            flags |= LO_FLAGS_PARTSCAN;
+        }
+
+        let mut info = LoopInfo64 {
+            lo_device: 0,
+            lo_inode: 0,
+            lo_rdevice: 0,
+            lo_offset: options.offset,
+            lo_sizelimit: options.sizelimit,
+            lo_number: number,
+            lo_encrypt_type: 0,
+            lo_encrypt_key_size: 0,
+            lo_flags: flags,
+            lo_file_name: [0; LO_NAME_SIZE],
+            lo_crypt_name: [0; LO_NAME_SIZE],
+            lo_encrypt_key: [0; LO_KEY_SIZE],
+            lo_init: [0; 2],
+        };
+
+        // The kernel only keeps a truncated copy of the name, for reporting purposes.
+        let name = backing_file.as_os_str().as_bytes();
+        let len = name.len().min(LO_NAME_SIZE - 1);
+        info.lo_file_name[..len].copy_from_slice(&name[..len]);
+
+        info
+    }
+}
+
+/// Options used when attaching a backing file to a loop device.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct LoopOptions {
+    /// Offset into the backing file, in bytes.
+    pub offset: u64,
+    /// Maximum size of the loop device, in bytes, or zero to use the whole backing file.
+    pub sizelimit: u64,
+    pub read_only: bool,
+    /// Detach the backing file automatically when the last user closes the device.
+    pub autoclear: bool,
+    /// Scan the loop device for partitions.
+    pub partscan: bool,
+    /// Bypass the page cache of the backing file.
+    pub direct_io: bool,
+}
+
+/// Allocates and releases loop devices through `/dev/loop-control`.
+pub struct LoopControl {
+    file: File,
+}
+
+impl LoopControl {
+    pub fn open() -> Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(LOOP_CONTROL)?;
+        Ok(Self { file })
+    }
+
+    /// Finds an unused loop device, creating one if none are available.
+    pub fn find_free(&self) -> Result<Block> {
+        let number = ioctl(&self.file, LOOP_CTL_GET_FREE, 0)?;
+        Block::new(&format!("loop{}", number))
+    }
+
+    /// Creates the loop device with the given number.
+    pub fn add(&self, number: u32) -> Result<Block> {
+        let number = ioctl(&self.file, LOOP_CTL_ADD, number as libc::c_ulong)?;
+        Block::new(&format!("loop{}", number))
+    }
+
+    /// Removes the loop device with the given number. The device must not be attached.
+    pub fn remove(&self, number: u32) -> Result<()> {
+        ioctl(&self.file, LOOP_CTL_REMOVE, number as libc::c_ulong).map(|_| ())
+    }
+
+    /// Attaches `backing_file` to the first free loop device.
+    pub fn attach<P: AsRef<Path>>(&self, backing_file: P, options: &LoopOptions) -> Result<Block> {
+        let mut attempt = 1;
+        loop {
+            let block = self.find_free()?;
+            match block.loop_attach(backing_file.as_ref(), options) {
+                Ok(()) => return Ok(block),
+                // Another process claimed the device in the meantime.
+                Err(why) if why.raw_os_error() == Some(libc::EBUSY) && attempt < ATTACH_RETRIES => {
+                    attempt += 1;
+                }
+                Err(why) => return Err(why),
+            }
+        }
+    }
+}
+
+impl Block {
+    /// Path to the device node of the block device in `/dev`.
+    pub fn dev_path(&self) -> PathBuf {
+        Path::new("/dev").join(self.id())
+    }
+
+    /// Loop device number, parsed from the name of the device.
+    pub fn loop_number(&self) -> Result<u32> {
+        self.id()
+            .strip_prefix("loop")
+            .and_then(|number| number.parse().ok())
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("{}: not a loop device", self.id()),
+                )
+            })
+    }
+
+    /// Attaches `backing_file` to this loop device.
+    pub fn loop_attach<P: AsRef<Path>>(
+        &self,
+        backing_file: P,
+        options: &LoopOptions,
+    ) -> Result<()> {
+        let backing_file = backing_file.as_ref();
+        let number = self.loop_number()?;
+
+        let backing = OpenOptions::new()
+            .read(true)
+            .write(!options.read_only)
+            .open(backing_file)?;
+        let device = OpenOptions::new()
+            .read(true)
+            .write(!options.read_only)
+            .open(self.dev_path())?;
+
+        ioctl(&device, LOOP_SET_FD, backing.as_raw_fd() as libc::c_ulong)?;
+
+        let mut info = LoopInfo64::new(number, backing_file, options);
+        let configured = ioctl(
+            &device,
+            LOOP_SET_STATUS64,
+            &mut info as *mut LoopInfo64 as libc::c_ulong,
+        )
+        .and_then(|_| {
+            if options.direct_io {
+                ioctl(&device, LOOP_SET_DIRECT_IO, 1)?;
+            }
+            Ok(())
+        });
+
+        if let Err(why) = configured {
+            let _ = ioctl(&device, LOOP_CLR_FD, 0);
+            return Err(why);
+        }
+
+        Ok(())
+    }
+
+    /// Detaches the backing file from this loop device.
+    pub fn loop_detach(&self) -> Result<()> {
+        self.loop_number()?;
+        let device = OpenOptions::new().read(true).open(self.dev_path())?;
+        ioctl(&device, LOOP_CLR_FD, 0).map(|_| ())
+    }
+
+    /// Enables or disables direct I/O on the backing file of this loop device.
+    pub fn loop_set_dio(&self, enable: bool) -> Result<()> {
+        self.loop_number()?;
+        let device = OpenOptions::new().read(true).open(self.dev_path())?;
+        ioctl(&device, LOOP_SET_DIRECT_IO, enable as libc::c_ulong).map(|_| ())
+    }
+}
+
+fn ioctl(file: &File, request: libc::c_ulong, arg: libc::c_ulong) -> Result<libc::c_int> {
+    let res = unsafe { libc::ioctl(file.as_raw_fd(), request as _, arg) };
+    if res < 0 {
+        Err(Error::last_os_error())
+    } else {
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use std::fs;
+    use std::mem;
+
+    fn block(name: &str) -> Block {
+        unsafe { Block::from_path_unchecked(Path::new("/sys/class/block").join(name)) }
+    }
+
+    #[test]
+    fn info_layout() {
+        // Must match `struct loop_info64` in include/uapi/linux/loop.h.
+        assert_eq!(mem::size_of::<LoopInfo64>(), 232);
+        assert_eq!(mem::align_of::<LoopInfo64>(), 8);
+
+        let info = LoopInfo64::new(0, Path::new(""), &LoopOptions::default());
+        let base = &info as *const LoopInfo64 as usize;
+        let offset = |field: *const u8| field as usize - base;
+        assert_eq!(offset(&info.lo_offset as *const u64 as *const u8), 24);
+        assert_eq!(offset(&info.lo_sizelimit as *const u64 as *const u8), 32);
+        assert_eq!(offset(&info.lo_number as *const u32 as *const u8), 40);
+        assert_eq!(offset(&info.lo_flags as *const u32 as *const u8), 52);
+        assert_eq!(offset(info.lo_file_name.as_ptr()), 56);
+        assert_eq!(offset(info.lo_crypt_name.as_ptr()), 120);
+        assert_eq!(offset(info.lo_encrypt_key.as_ptr()), 184);
+        assert_eq!(offset(info.lo_init.as_ptr() as *const u8), 216);
+    }
+
+    #[test]
+    fn info_flags() {
+        let path = Path::new("/tmp/backing.img");
+        let info = LoopInfo64::new(3, path, &LoopOptions::default());
+        assert_eq!(info.lo_flags, 0);
+        assert_eq!(info.lo_number, 3);
+
+        let options = LoopOptions {
+            offset: 4096,
+            sizelimit: 1 << 20,
+            read_only: true,
+            autoclear: true,
+            partscan: true,
+            direct_io: true,
+        };
+        let info = LoopInfo64::new(7, path, &options);
+        assert_eq!(
+            info.lo_flags,
+            LO_FLAGS_READ_ONLY | LO_FLAGS_AUTOCLEAR | LO_FLAGS_PARTSCAN
+        );
+        assert_eq!(info.lo_offset, 4096);
+        assert_eq!(info.lo_sizelimit, 1 << 20);
+
+        let options = LoopOptions {
+            partscan: true,
+            ..LoopOptions::default()
+        };
+        assert_eq!(
+            LoopInfo64::new(0, path, &options).lo_flags,
+            LO_FLAGS_PARTSCAN
+        );
+    }
+
+    #[test]
+    fn info_file_name() {
+        let info = LoopInfo64::new(0, Path::new("/tmp/backing.img"), &LoopOptions::default());
+        assert_eq!(&info.lo_file_name[..16], b"/tmp/backing.img");
+        assert!(info.lo_file_name[16..].iter().all(|&byte| byte == 0));
+
+        // Long names are truncated, leaving room for the terminating nul.
+        let long = format!("/{}", "a".repeat(100));
+        let info = LoopInfo64::new(0, Path::new(&long), &LoopOptions::default());
+        assert_eq!(
+            &info.lo_file_name[..LO_NAME_SIZE - 1],
+            &long.as_bytes()[..LO_NAME_SIZE - 1]
+        );
+        assert_eq!(info.lo_file_name[LO_NAME_SIZE - 1], 0);
+    }
+
+    #[test]
+    fn loop_number() {
+        assert_eq!(block("loop0").loop_number().unwrap(), 0);
+        assert_eq!(block("loop12").loop_number().unwrap(), 12);
+
+        for name in &["sda", "loop", "loopx", "loop1p1", "nvme0n1"] {
+            let err = block(name).loop_number().unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        }
+    }
+
+    /// A 1 MiB backing file inside `dir`.
+    fn backing_file(dir: &TempDir) -> PathBuf {
+        let path = dir.path().join("backing.img");
+        fs::write(&path, vec![0; 1 << 20]).unwrap();
+        path
+    }
+
+    #[test]
+    #[ignore = "needs root and /dev/loop-control"]
+    fn attach_detach() {
+        let dir = TempDir::new("loop-attach");
+        let path = backing_file(&dir);
+        let control = LoopControl::open().unwrap();
+        let options = LoopOptions {
+            offset: 4096,
+            read_only: true,
+            ..LoopOptions::default()
+        };
+
+        let block = control.attach(&path, &options).unwrap();
+        let attached = (block.loop_backing_file(), block.loop_offset(), block.ro());
+        block.loop_detach().unwrap();
+
+        assert_eq!(attached.0.unwrap(), path.to_str().unwrap());
+        assert_eq!(attached.1.unwrap(), 4096);
+        assert_eq!(attached.2.unwrap(), 1);
+    }
+
+    #[test]
+    #[ignore = "needs root and /dev/loop-control"]
+    fn detach_unattached() {
+        let control = LoopControl::open().unwrap();
+        let block = control.find_free().unwrap();
+        assert_eq!(
+            block.loop_detach().unwrap_err().raw_os_error(),
+            Some(libc::ENXIO)
+        );
+    }
+}
@@ -1,8 +1,25 @@
-use crate::SysClass;
-use std::io::Result;
+use crate::{RuntimePM, RuntimePMExt, RuntimePowerManagement, SysClass};
+use std::fmt;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
+pub use self::discard::DiscardSupport;
+mod discard;
+
+pub use self::loop_device::{LoopControl, LoopOptions};
+mod loop_device;
+
+pub use self::zram::{
+    Zram, ZramBdStat, ZramCompAlgorithm, ZramControl, ZramIoStat, ZramMmStat, ZramWriteback,
+};
+mod zram;
+
+/// The kernel always reports `start` and `size` in 512-byte sectors, regardless of the
+/// logical block size of the underlying device.
+const SECTOR_SIZE: u64 = 512;
+
 // SCSI device types. Copied almost as-is from kernel header.
 // https://git.kernel.org/pub/scm/linux/kernel/git/torvalds/linux.git/tree/include/scsi/scsi_proto.h
 const SCSI_TYPE_DISK: u8 = 0x00;
@@ -147,7 +164,7 @@ impl Block {
     }
 
     pub fn device_type(&self) -> BlockDeviceType {
-        let name = self.path.file_name().map(|name| name.to_str()).flatten();
+        let name = self.path.file_name().and_then(|name| name.to_str());
         if self.partition().is_ok() {
             BlockDeviceType::Partition
         } else if name.is_some_and(|name| {
@@ -190,7 +207,7 @@ impl Block {
             .into_iter()
             .filter(|x| {
                 x.parent_device()
-                    .map_or(false, |parent| parent.path() == self.path)
+                    .is_some_and(|parent| parent.path() == self.path)
             })
             .collect::<Vec<_>>();
         children.sort_unstable();
@@ -227,6 +244,124 @@ impl Block {
         }
     }
 
+    /// Read a single `KEY=value` variable from the `uevent` file.
+    pub fn uevent_var(&self, key: &str) -> Result<Option<String>> {
+        Ok(self.uevent()?.lines().find_map(|line| {
+            let (k, v) = line.split_once('=')?;
+            if k == key {
+                Some(v.to_owned())
+            } else {
+                None
+            }
+        }))
+    }
+
+    // Partition metadata
+
+    /// The whole disk that a partition belongs to, found from its place in the sysfs hierarchy.
+    ///
+    /// Returns `self` for devices which are not partitions.
+    pub fn whole_disk(&self) -> Result<Block> {
+        if self.partition().is_err() {
+            return Ok(self.clone());
+        }
+
+        let path = fs::canonicalize(&self.path)?;
+        let name = path
+            .parent()
+            .and_then(Path::file_name)
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::NotFound,
+                    format!("{}: partition has no parent disk", self.path.display()),
+                )
+            })?;
+
+        Block::new(name)
+    }
+
+    /// The partition number, from `PARTN` in uevent.
+    pub fn partn(&self) -> Result<u32> {
+        match self.uevent_var("PARTN")? {
+            Some(partn) => partn
+                .parse()
+                .map_err(|err| Error::new(ErrorKind::InvalidData, format!("PARTN: {}", err))),
+            None => self.parse_file("partition"),
+        }
+    }
+
+    /// The partition name, from `PARTNAME` in uevent. Only GPT and a few other schemes
+    /// store partition names.
+    pub fn partname(&self) -> Result<Option<String>> {
+        self.uevent_var("PARTNAME")
+    }
+
+    /// The partition UUID, from `PARTUUID` in uevent.
+    pub fn partuuid(&self) -> Result<Option<String>> {
+        self.uevent_var("PARTUUID")
+    }
+
+    /// The partition table scheme, inferred from the format of the partition UUID.
+    pub fn partition_table_scheme(&self) -> Result<PartitionTableScheme> {
+        Ok(self
+            .partuuid()?
+            .map_or(PartitionTableScheme::Unknown, |uuid| {
+                PartitionTableScheme::from_partuuid(&uuid)
+            }))
+    }
+
+    /// The logical block size of the device, taken from the whole disk for partitions.
+    pub fn logical_block_size(&self) -> Result<u64> {
+        self.whole_disk()?.queue_logical_block_size()
+    }
+
+    /// The physical block size of the device, taken from the whole disk for partitions.
+    pub fn physical_block_size(&self) -> Result<u64> {
+        self.whole_disk()?.queue_physical_block_size()
+    }
+
+    /// Offset of the partition from the start of the whole disk.
+    pub fn start_bytes(&self) -> Result<ByteSize> {
+        Ok(ByteSize(self.start()? * SECTOR_SIZE))
+    }
+
+    /// Offset of the partition from the start of the whole disk, in logical blocks.
+    pub fn start_logical_blocks(&self) -> Result<u64> {
+        Ok(self.start_bytes()?.0 / self.logical_block_size()?)
+    }
+
+    /// Size of the device.
+    pub fn size_bytes(&self) -> Result<ByteSize> {
+        Ok(ByteSize(self.size()? * SECTOR_SIZE))
+    }
+
+    /// Size of the device, in logical blocks.
+    pub fn size_logical_blocks(&self) -> Result<u64> {
+        Ok(self.size_bytes()?.0 / self.logical_block_size()?)
+    }
+
+    /// Checks the start of a partition against the physical block size and the alignment
+    /// offset of the whole disk.
+    pub fn partition_alignment(&self) -> Result<PartitionAlignment> {
+        let disk = self.whole_disk()?;
+        let physical_block_size = disk.queue_physical_block_size()?;
+        if physical_block_size == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "physical block size is zero",
+            ));
+        }
+
+        let disk_offset = disk.alignment_offset()? % physical_block_size;
+        let start = self.start_bytes()?.0 % physical_block_size;
+
+        Ok(PartitionAlignment {
+            physical_block_size,
+            offset: (physical_block_size + start - disk_offset) % physical_block_size,
+        })
+    }
+
     // Base properties
 
     method!(alignment_offset parse_file u64);
@@ -309,6 +444,20 @@ impl Block {
 
     // integrity
 
+    // loop
+
+    method!("loop/autoclear", loop_autoclear parse_file u8);
+
+    method!("loop/backing_file", loop_backing_file trim_file String);
+
+    method!("loop/dio", loop_dio parse_file u8);
+
+    method!("loop/offset", loop_offset parse_file u64);
+
+    method!("loop/partscan", loop_partscan parse_file u8);
+
+    method!("loop/sizelimit", loop_sizelimit parse_file u64);
+
     // md
 
     method!("md/array_size", md_array_size read_file String);
@@ -425,23 +574,8 @@ impl Block {
 
     // method!("queue/scheduler", queue_scheduler parse_file u64);
     pub fn queue_scheduler(&self) -> Result<BlockScheduler> {
-        let mut active = 0;
-        let mut schedules = Vec::new();
-        for schedule in self.read_file("queue/scheduler")?.split_whitespace() {
-            let schedule = if schedule.starts_with('[') {
-                active = schedules.len();
-                &schedule[1..schedule.len() - 1]
-            } else {
-                schedule
-            };
-
-            schedules.push(schedule.to_owned());
-        }
-
-        Ok(BlockScheduler {
-            active: active as u8,
-            schedules,
-        })
+        let (schedules, active) = parse_bracketed_list(&self.read_file("queue/scheduler")?);
+        Ok(BlockScheduler { active, schedules })
     }
 
     method!("queue/write_cache", queue_write_cache read_file String);
@@ -489,6 +623,40 @@ impl Block {
     method!("queue/iosched/target_latency_us", queue_iosched_target_latency_us parse_file u64);
 }
 
+/// Runtime power management of the underlying device, such as the SCSI disk behind `sda`.
+///
+/// Devices without an underlying device, such as partitions and virtual devices, do not
+/// have these attributes.
+impl RuntimePM for Block {
+    fn set_runtime_pm(&self, state: RuntimePowerManagement) -> Result<()> {
+        self.write_file("device/power/control", <&'static str>::from(state))
+    }
+}
+
+impl RuntimePMExt for Block {
+    fn power_dir(&self) -> &'static str {
+        "device/power"
+    }
+}
+
+/// Parses a list such as `mq-deadline [kyber] none`, where the active entry is bracketed.
+fn parse_bracketed_list(list: &str) -> (Vec<String>, u8) {
+    let mut active = 0;
+    let mut entries = Vec::new();
+    for entry in list.split_whitespace() {
+        let entry = if entry.starts_with('[') {
+            active = entries.len();
+            &entry[1..entry.len() - 1]
+        } else {
+            entry
+        };
+
+        entries.push(entry.to_owned());
+    }
+
+    (entries, active as u8)
+}
+
 pub struct BlockScheduler {
     schedules: Vec<String>,
     active: u8,
@@ -503,3 +671,162 @@ impl BlockScheduler {
         &self.schedules
     }
 }
+
+/// The partition table scheme that a partition was found in.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum PartitionTableScheme {
+    /// GUID Partition Table, with a full UUID per partition.
+    Gpt,
+    /// Master Boot Record, with the disk signature and partition number as UUID.
+    Mbr,
+    Unknown,
+}
+
+impl PartitionTableScheme {
+    /// GPT partitions carry a full 36-character UUID, whereas MBR partitions are given
+    /// `SSSSSSSS-PP`, made of the disk signature and the partition number.
+    pub fn from_partuuid(uuid: &str) -> Self {
+        let is_hex = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_hexdigit());
+        let parts = uuid.split('-').collect::<Vec<_>>();
+        match parts.as_slice() {
+            [a, b, c, d, e]
+                if a.len() == 8
+                    && b.len() == 4
+                    && c.len() == 4
+                    && d.len() == 4
+                    && e.len() == 12
+                    && parts.iter().all(|part| is_hex(part)) =>
+            {
+                PartitionTableScheme::Gpt
+            }
+            [signature, number] if signature.len() == 8 && is_hex(signature) && is_hex(number) => {
+                PartitionTableScheme::Mbr
+            }
+            _ => PartitionTableScheme::Unknown,
+        }
+    }
+}
+
+/// Alignment of a partition relative to the physical blocks of its disk.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PartitionAlignment {
+    pub physical_block_size: u64,
+    /// Number of bytes by which the partition start is past a physical block boundary.
+    pub offset: u64,
+}
+
+impl PartitionAlignment {
+    pub fn is_aligned(&self) -> bool {
+        self.offset == 0
+    }
+}
+
+/// A size in bytes, displayed in human-readable binary units.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub struct ByteSize(pub u64);
+
+impl ByteSize {
+    pub fn as_u64(self) -> u64 {
+        self.0
+    }
+}
+
+impl From<ByteSize> for u64 {
+    fn from(size: ByteSize) -> Self {
+        size.0
+    }
+}
+
+impl fmt::Display for ByteSize {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const UNITS: [&str; 7] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+
+        if self.0 < 1024 {
+            return write!(f, "{} B", self.0);
+        }
+
+        let mut value = self.0 as f64;
+        let mut unit = 0;
+        while value >= 1024.0 && unit < UNITS.len() - 1 {
+            value /= 1024.0;
+            unit += 1;
+        }
+
+        write!(f, "{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn partuuid_scheme() {
+        assert_eq!(
+            PartitionTableScheme::from_partuuid("1c6d6a7b-47c3-4c6e-9a3d-2f1b5e8c9d0a"),
+            PartitionTableScheme::Gpt
+        );
+        assert_eq!(
+            PartitionTableScheme::from_partuuid("1C6D6A7B-47C3-4C6E-9A3D-2F1B5E8C9D0A"),
+            PartitionTableScheme::Gpt
+        );
+        assert_eq!(
+            PartitionTableScheme::from_partuuid("a1b2c3d4-01"),
+            PartitionTableScheme::Mbr
+        );
+        assert_eq!(
+            PartitionTableScheme::from_partuuid("a1b2c3d4-0a"),
+            PartitionTableScheme::Mbr
+        );
+        assert_eq!(
+            PartitionTableScheme::from_partuuid(""),
+            PartitionTableScheme::Unknown
+        );
+        assert_eq!(
+            PartitionTableScheme::from_partuuid("a1b2c3d4-"),
+            PartitionTableScheme::Unknown
+        );
+        assert_eq!(
+            PartitionTableScheme::from_partuuid("a1b2c3-01"),
+            PartitionTableScheme::Unknown
+        );
+        assert_eq!(
+            PartitionTableScheme::from_partuuid("1c6d6a7b-47c3-4c6e-9a3d-2f1b5e8c9d0g"),
+            PartitionTableScheme::Unknown
+        );
+        assert_eq!(
+            PartitionTableScheme::from_partuuid("1c6d6a7b47c34c6e9a3d2f1b5e8c9d0a"),
+            PartitionTableScheme::Unknown
+        );
+    }
+
+    #[test]
+    fn byte_size_display() {
+        assert_eq!(ByteSize(0).to_string(), "0 B");
+        assert_eq!(ByteSize(1023).to_string(), "1023 B");
+        assert_eq!(ByteSize(1024).to_string(), "1.0 KiB");
+        assert_eq!(ByteSize(1536).to_string(), "1.5 KiB");
+        assert_eq!(ByteSize(512 * 1024 * 1024).to_string(), "512.0 MiB");
+        assert_eq!(ByteSize(1 << 40).to_string(), "1.0 TiB");
+        assert_eq!(ByteSize(u64::MAX).to_string(), "16.0 EiB");
+    }
+
+    #[test]
+    fn partition_alignment() {
+        let dir = TempDir::new("block-alignment");
+        fs::create_dir(dir.path().join("queue")).unwrap();
+        fs::write(dir.path().join("alignment_offset"), "0\n").unwrap();
+        fs::write(dir.path().join("start"), "2049\n").unwrap();
+        let block = unsafe { Block::from_path_unchecked(dir.path().to_path_buf()) };
+
+        fs::write(dir.path().join("queue/physical_block_size"), "4096\n").unwrap();
+        let alignment = block.partition_alignment().unwrap();
+        assert_eq!(alignment.physical_block_size, 4096);
+        assert_eq!(alignment.offset, 512);
+
+        fs::write(dir.path().join("queue/physical_block_size"), "0\n").unwrap();
+        let err = block.partition_alignment().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,364 @@
+use super::parse_bracketed_list;
+use crate::{Block, SysClass};
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::str::FromStr;
+
+const ZRAM_CONTROL: &str = "/sys/class/zram-control";
+
+/// Configuration and statistics of a compressed RAM disk.
+pub struct Zram<'a> {
+    parent: &'a Block,
+}
+
+impl Block {
+    pub fn zram(&self) -> Zram<'_> {
+        Zram { parent: self }
+    }
+}
+
+impl<'a> Zram<'a> {
+    /// Size of the uncompressed device, in bytes.
+    pub fn disksize(&self) -> Result<u64> {
+        self.parent.parse_file("disksize")
+    }
+
+    /// Sets the size of the uncompressed device. The device must be reset first if it
+    /// has already been initialized.
+    pub fn set_disksize(&self, size: u64) -> Result<()> {
+        self.parent.write_file("disksize", size.to_string())
+    }
+
+    pub fn comp_algorithm(&self) -> Result<ZramCompAlgorithm> {
+        let list = self.parent.read_file("comp_algorithm")?;
+        if !list.split_whitespace().any(|entry| entry.starts_with('[')) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "comp_algorithm: no active algorithm",
+            ));
+        }
+
+        let (algorithms, active) = parse_bracketed_list(&list);
+        Ok(ZramCompAlgorithm { algorithms, active })
+    }
+
+    /// Selects the compression algorithm. Must be set before `disksize`.
+    pub fn set_comp_algorithm(&self, algorithm: &str) -> Result<()> {
+        self.parent.write_file("comp_algorithm", algorithm)
+    }
+
+    /// Maximum amount of memory used to store compressed data, in bytes, or zero for no limit.
+    pub fn mem_limit(&self) -> Result<u64> {
+        Ok(self.mm_stat()?.mem_limit)
+    }
+
+    pub fn set_mem_limit(&self, limit: u64) -> Result<()> {
+        self.parent.write_file("mem_limit", limit.to_string())
+    }
+
+    pub fn max_comp_streams(&self) -> Result<u32> {
+        self.parent.parse_file("max_comp_streams")
+    }
+
+    /// Device used for writing back idle or incompressible pages.
+    pub fn backing_dev(&self) -> Result<String> {
+        self.parent.trim_file("backing_dev")
+    }
+
+    pub fn set_backing_dev<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.parent
+            .write_file("backing_dev", path.as_ref().as_os_str().as_bytes())
+    }
+
+    pub fn mm_stat(&self) -> Result<ZramMmStat> {
+        self.parent.parse_file("mm_stat")
+    }
+
+    pub fn io_stat(&self) -> Result<ZramIoStat> {
+        self.parent.parse_file("io_stat")
+    }
+
+    /// Statistics of the backing device, only available when writeback is configured.
+    pub fn bd_stat(&self) -> Result<ZramBdStat> {
+        self.parent.parse_file("bd_stat")
+    }
+
+    /// Frees all memory of the device and returns it to the uninitialized state.
+    pub fn reset(&self) -> Result<()> {
+        self.parent.write_file("reset", "1")
+    }
+
+    /// Marks all pages currently stored as idle, for use with `ZramWriteback::Idle`.
+    pub fn mark_idle(&self) -> Result<()> {
+        self.parent.write_file("idle", "all")
+    }
+
+    /// Writes pages out to the backing device.
+    pub fn writeback(&self, pages: ZramWriteback) -> Result<()> {
+        self.parent
+            .write_file("writeback", <&'static str>::from(pages))
+    }
+}
+
+/// Compression algorithms supported by a zram device, and the active one.
+#[derive(Clone, Debug)]
+pub struct ZramCompAlgorithm {
+    algorithms: Vec<String>,
+    active: u8,
+}
+
+impl ZramCompAlgorithm {
+    pub fn active(&self) -> &str {
+        &self.algorithms[self.active as usize]
+    }
+
+    pub fn algorithms(&self) -> &[String] {
+        &self.algorithms
+    }
+}
+
+/// Pages to write back to the backing device of a zram device.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ZramWriteback {
+    Idle,
+    Huge,
+    HugeIdle,
+    Incompressible,
+}
+
+impl From<ZramWriteback> for &'static str {
+    fn from(pages: ZramWriteback) -> &'static str {
+        match pages {
+            ZramWriteback::Idle => "idle",
+            ZramWriteback::Huge => "huge",
+            ZramWriteback::HugeIdle => "huge_idle",
+            ZramWriteback::Incompressible => "incompressible",
+        }
+    }
+}
+
+/// Memory statistics from `mm_stat`. Sizes are in bytes, counts are in pages.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ZramMmStat {
+    /// Uncompressed size of the data stored.
+    pub orig_data_size: u64,
+    /// Compressed size of the data stored.
+    pub compr_data_size: u64,
+    /// Memory allocated for the device, including fragmentation and metadata.
+    pub mem_used_total: u64,
+    pub mem_limit: u64,
+    pub mem_used_max: u64,
+    /// Pages which are filled with a single value and not allocated.
+    pub same_pages: u64,
+    pub pages_compacted: u64,
+    /// Pages which could not be compressed.
+    pub huge_pages: u64,
+    /// Pages which could not be compressed since the device was created. Not present on
+    /// older kernels.
+    pub huge_pages_since: Option<u64>,
+}
+
+impl ZramMmStat {
+    /// Ratio of the uncompressed size to the compressed size of the stored data.
+    pub fn compression_ratio(&self) -> Option<f64> {
+        if self.compr_data_size == 0 {
+            None
+        } else {
+            Some(self.orig_data_size as f64 / self.compr_data_size as f64)
+        }
+    }
+}
+
+impl FromStr for ZramMmStat {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        let values = parse_stat("mm_stat", s, 8)?;
+        Ok(ZramMmStat {
+            orig_data_size: values[0],
+            compr_data_size: values[1],
+            mem_used_total: values[2],
+            mem_limit: values[3],
+            mem_used_max: values[4],
+            same_pages: values[5],
+            pages_compacted: values[6],
+            huge_pages: values[7],
+            huge_pages_since: values.get(8).copied(),
+        })
+    }
+}
+
+/// Failure statistics from `io_stat`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ZramIoStat {
+    pub failed_reads: u64,
+    pub failed_writes: u64,
+    /// Requests which were not aligned to the page size.
+    pub invalid_io: u64,
+    /// Free slot notifications from swap.
+    pub notify_free: u64,
+}
+
+impl FromStr for ZramIoStat {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        let values = parse_stat("io_stat", s, 4)?;
+        Ok(ZramIoStat {
+            failed_reads: values[0],
+            failed_writes: values[1],
+            invalid_io: values[2],
+            notify_free: values[3],
+        })
+    }
+}
+
+/// Backing device statistics from `bd_stat`, counted in pages.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ZramBdStat {
+    /// Pages currently stored on the backing device.
+    pub bd_count: u64,
+    pub bd_reads: u64,
+    pub bd_writes: u64,
+}
+
+impl FromStr for ZramBdStat {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        let values = parse_stat("bd_stat", s, 3)?;
+        Ok(ZramBdStat {
+            bd_count: values[0],
+            bd_reads: values[1],
+            bd_writes: values[2],
+        })
+    }
+}
+
+fn parse_stat(name: &str, s: &str, min_len: usize) -> Result<Vec<u64>> {
+    let values = s
+        .split_whitespace()
+        .map(u64::from_str)
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|err| Error::new(ErrorKind::InvalidData, format!("{}: {}", name, err)))?;
+
+    if values.len() < min_len {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "{}: expected {} values, found {}",
+                name,
+                min_len,
+                values.len()
+            ),
+        ));
+    }
+
+    Ok(values)
+}
+
+/// Creates and removes zram devices through `/sys/class/zram-control`.
+pub struct ZramControl;
+
+impl ZramControl {
+    /// Creates a new zram device.
+    pub fn hot_add() -> Result<Block> {
+        let id = fs::read_to_string(Path::new(ZRAM_CONTROL).join("hot_add"))?;
+        Block::new(&format!("zram{}", id.trim()))
+    }
+
+    /// Removes a zram device. The device must not be in use.
+    pub fn hot_remove(block: &Block) -> Result<()> {
+        let id = block.id().strip_prefix("zram").ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("{}: not a zram device", block.id()),
+            )
+        })?;
+
+        fs::write(Path::new(ZRAM_CONTROL).join("hot_remove"), id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn mm_stat() {
+        let stat: ZramMmStat = "8192 2048 12288 0 12288 1 0 0\n".parse().unwrap();
+        assert_eq!(stat.orig_data_size, 8192);
+        assert_eq!(stat.compr_data_size, 2048);
+        assert_eq!(stat.mem_used_total, 12288);
+        assert_eq!(stat.mem_limit, 0);
+        assert_eq!(stat.mem_used_max, 12288);
+        assert_eq!(stat.same_pages, 1);
+        assert_eq!(stat.huge_pages, 0);
+        assert_eq!(stat.huge_pages_since, None);
+        assert_eq!(stat.compression_ratio(), Some(4.0));
+
+        let stat: ZramMmStat = "0 0 0 0 0 0 0 3 5\n".parse().unwrap();
+        assert_eq!(stat.huge_pages, 3);
+        assert_eq!(stat.huge_pages_since, Some(5));
+        assert_eq!(stat.compression_ratio(), None);
+
+        for short in &["", "1 2 3 4 5 6 7", "1 2 3 4 5 6 7 x"] {
+            let err = short.parse::<ZramMmStat>().unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn io_stat() {
+        let stat: ZramIoStat = "1 2 3 4\n".parse().unwrap();
+        assert_eq!(
+            stat,
+            ZramIoStat {
+                failed_reads: 1,
+                failed_writes: 2,
+                invalid_io: 3,
+                notify_free: 4,
+            }
+        );
+
+        let err = "1 2 3".parse::<ZramIoStat>().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn bd_stat() {
+        let stat: ZramBdStat = "10 20 30\n".parse().unwrap();
+        assert_eq!(
+            stat,
+            ZramBdStat {
+                bd_count: 10,
+                bd_reads: 20,
+                bd_writes: 30,
+            }
+        );
+
+        let err = "10 20".parse::<ZramBdStat>().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn comp_algorithm() {
+        let dir = TempDir::new("zram-comp-algorithm");
+        let block = unsafe { Block::from_path_unchecked(dir.path().to_path_buf()) };
+
+        fs::write(
+            dir.path().join("comp_algorithm"),
+            "lzo lzo-rle [lz4] zstd\n",
+        )
+        .unwrap();
+        let algorithm = block.zram().comp_algorithm().unwrap();
+        assert_eq!(algorithm.active(), "lz4");
+        assert_eq!(algorithm.algorithms(), ["lzo", "lzo-rle", "lz4", "zstd"]);
+
+        for list in &["lzo lzo-rle lz4\n", "\n"] {
+            fs::write(dir.path().join("comp_algorithm"), list).unwrap();
+            let err = block.zram().comp_algorithm().unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidData);
+        }
+    }
+}
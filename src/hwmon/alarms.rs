@@ -0,0 +1,203 @@
+use crate::{HwMon, SysClass};
+use std::fmt;
+use std::io::Result;
+
+/// Alarm, fault, beep and enable flags, which a sensor of any type may have.
+///
+/// Alarms are raised by the chip itself, by comparing its readings against the limits
+/// which are programmed into it.
+pub trait HwMonAlarms {
+    /// The device of the sensor, and the name of the attribute for `item`.
+    fn item_attribute(&self, item: &str) -> (&HwMon, String);
+
+    fn flag(&self, item: &str) -> Result<u8> {
+        let (hwmon, attribute) = self.item_attribute(item);
+        hwmon.parse_file(attribute)
+    }
+
+    fn set_flag(&self, item: &str, value: bool) -> Result<()> {
+        let (hwmon, attribute) = self.item_attribute(item);
+        hwmon.write_file(attribute, if value { "1" } else { "0" })
+    }
+
+    fn alarm(&self) -> Result<u8> {
+        self.flag("alarm")
+    }
+
+    fn min_alarm(&self) -> Result<u8> {
+        self.flag("min_alarm")
+    }
+
+    fn max_alarm(&self) -> Result<u8> {
+        self.flag("max_alarm")
+    }
+
+    fn lcrit_alarm(&self) -> Result<u8> {
+        self.flag("lcrit_alarm")
+    }
+
+    fn crit_alarm(&self) -> Result<u8> {
+        self.flag("crit_alarm")
+    }
+
+    /// Whether the sensor is faulty, such as a disconnected diode or a stalled fan.
+    fn fault(&self) -> Result<u8> {
+        self.flag("fault")
+    }
+
+    fn beep(&self) -> Result<u8> {
+        self.flag("beep")
+    }
+
+    fn set_beep(&self, beep: bool) -> Result<()> {
+        self.set_flag("beep", beep)
+    }
+
+    /// Whether the sensor is enabled. Disabled sensors do not report readings or alarms.
+    fn enable(&self) -> Result<u8> {
+        self.flag("enable")
+    }
+
+    fn set_enable(&self, enable: bool) -> Result<()> {
+        self.set_flag("enable", enable)
+    }
+}
+
+/// An alarm or fault flag of a sensor, from `HwMon::active_alarms`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HwMonAlarm {
+    /// The type of the sensor, such as `temp`.
+    pub kind: String,
+    pub id: u64,
+    /// The flag, such as `max_alarm` or `fault`.
+    pub item: String,
+    pub label: Option<String>,
+}
+
+impl fmt::Display for HwMonAlarm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}_{}", self.kind, self.id, self.item)?;
+        if let Some(ref label) = self.label {
+            write!(f, " ({})", label)?;
+        }
+        Ok(())
+    }
+}
+
+/// The alarm and fault flags of a device which are set, and those which could not be read.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct HwMonActiveAlarms {
+    pub alarms: Vec<HwMonAlarm>,
+    /// Flags which failed to read or parse, so whether they are set is unknown.
+    pub unreadable: Vec<HwMonAlarm>,
+}
+
+impl HwMon {
+    pub fn beep_enable(&self) -> Result<u8> {
+        self.parse_file("beep_enable")
+    }
+
+    /// Enables or disables beeping for the whole chip.
+    pub fn set_beep_enable(&self, enable: bool) -> Result<()> {
+        self.write_file("beep_enable", if enable { "1" } else { "0" })
+    }
+
+    /// Every alarm and fault flag of every sensor which is set.
+    ///
+    /// Some drivers fail reads of channels which are disabled, so a flag which cannot be
+    /// read does not stop the others from being checked. It is listed as unreadable
+    /// instead, as it may hide an alarm.
+    pub fn active_alarms(&self) -> Result<HwMonActiveAlarms> {
+        let mut active = HwMonActiveAlarms::default();
+        for sensor in self.sensors()? {
+            for item in sensor.items() {
+                if !(item.ends_with("alarm") || item == "fault") {
+                    continue;
+                }
+
+                let alarm = || HwMonAlarm {
+                    kind: sensor.kind().to_owned(),
+                    id: sensor.id(),
+                    item: item.clone(),
+                    label: sensor.label().ok(),
+                };
+
+                match sensor.parse::<u8>(item) {
+                    Ok(0) => (),
+                    Ok(_) => active.alarms.push(alarm()),
+                    Err(_) => active.unreadable.push(alarm()),
+                }
+            }
+        }
+
+        Ok(active)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use std::fs;
+
+    #[test]
+    fn active_alarms() {
+        let dir = TempDir::new("alarms");
+        for (file, value) in &[
+            ("name", "nct6775"),
+            ("temp1_input", "95000"),
+            ("temp1_label", "CPU"),
+            ("temp1_max_alarm", "1"),
+            ("temp1_crit_alarm", "0"),
+            ("temp2_input", "0"),
+            ("temp2_alarm", "garbage"),
+            ("fan1_input", "0"),
+            ("fan1_fault", "1"),
+            ("in0_input", "1200"),
+            ("in0_alarm", "0"),
+        ] {
+            fs::write(dir.path().join(file), value).unwrap();
+        }
+        // Reading a directory fails, like a disabled channel which returns EIO.
+        fs::create_dir(dir.path().join("in1_alarm")).unwrap();
+
+        let hwmon = unsafe { HwMon::from_path_unchecked(dir.path().to_owned()) };
+        let active = hwmon.active_alarms().unwrap();
+        let alarms = active.alarms;
+        assert_eq!(
+            alarms,
+            vec![
+                HwMonAlarm {
+                    kind: "fan".to_owned(),
+                    id: 1,
+                    item: "fault".to_owned(),
+                    label: None,
+                },
+                HwMonAlarm {
+                    kind: "temp".to_owned(),
+                    id: 1,
+                    item: "max_alarm".to_owned(),
+                    label: Some("CPU".to_owned()),
+                },
+            ]
+        );
+        assert_eq!(alarms[1].to_string(), "temp1_max_alarm (CPU)");
+        assert_eq!(
+            active.unreadable,
+            vec![
+                HwMonAlarm {
+                    kind: "in".to_owned(),
+                    id: 1,
+                    item: "alarm".to_owned(),
+                    label: None,
+                },
+                HwMonAlarm {
+                    kind: "temp".to_owned(),
+                    id: 2,
+                    item: "alarm".to_owned(),
+                    label: None,
+                },
+            ]
+        );
+    }
+}
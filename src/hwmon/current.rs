@@ -0,0 +1,37 @@
+use crate::{Amps, HwMon, HwMonAlarms, HwMonSensor};
+use std::io::Result;
+
+hwmon_sensor!(
+    /// A current sensor, `currN`, with values in milliamperes.
+    HwMonCurrent, "curr", |s| s.input()
+);
+
+impl<'a> HwMonCurrent<'a> {
+    hwmon_items! {
+        /// Measured value, in milliamperes.
+        input -> i32;
+        min -> i32;
+        max -> i32;
+        lcrit -> i32;
+        crit -> i32;
+        average -> i32;
+        lowest -> i32;
+        highest -> i32;
+        rated_min -> i32;
+        rated_max -> i32;
+    }
+
+    hwmon_scaled! {
+        Amps::from_milliamps;
+        input => input_amps;
+        min => min_amps;
+        max => max_amps;
+        lcrit => lcrit_amps;
+        crit => crit_amps;
+        average => average_amps;
+        lowest => lowest_amps;
+        highest => highest_amps;
+        rated_min => rated_min_amps;
+        rated_max => rated_max_amps;
+    }
+}
@@ -0,0 +1,19 @@
+use crate::{HwMon, HwMonAlarms, HwMonSensor, Joules};
+use std::io::Result;
+
+hwmon_sensor!(
+    /// An energy sensor, `energyN`, with values in microjoules.
+    HwMonEnergy, "energy", |s| s.input()
+);
+
+impl<'a> HwMonEnergy<'a> {
+    hwmon_items! {
+        /// Cumulative energy use, in microjoules.
+        input -> u64;
+    }
+
+    hwmon_scaled! {
+        Joules::from_microjoules;
+        input => input_joules;
+    }
+}
@@ -1,45 +1,109 @@
-use crate::{HwMon, SysClass};
+use crate::{HwMon, HwMonAlarms, HwMonSensor, Rpm, SysClass};
 use std::io::Result;
 
 pub struct HwMonFan<'a> {
     hwmon: &'a HwMon,
     id: u64,
+    sensor: HwMonSensor<'a>,
 }
 
 impl<'a> HwMonFan<'a> {
     pub fn new(hwmon: &'a HwMon, id: u64) -> Result<Self> {
-        let s = Self { hwmon, id };
+        let s = Self::from_sensor(hwmon.sensor("fan", id)?);
 
         s.input()?;
 
         Ok(s)
     }
 
+    pub(crate) fn from_sensor(sensor: HwMonSensor<'a>) -> Self {
+        Self {
+            hwmon: sensor.hwmon(),
+            id: sensor.id(),
+            sensor,
+        }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// The sensor with every item it supports, as discovered when this was created.
+    pub fn sensor(&self) -> &HwMonSensor<'a> {
+        &self.sensor
+    }
+
+    /// The label from the sensors config of the device, or else from the chip.
     pub fn label(&self) -> Result<String> {
-        self.hwmon.trim_file(&format!("fan{}_label", self.id))
+        if let Some(label) = self.hwmon.configured_label("fan", self.id) {
+            return Ok(label);
+        }
+
+        self.hwmon.trim_file(format!("fan{}_label", self.id))
     }
 
     pub fn input(&self) -> Result<u32> {
-        self.hwmon.parse_file(&format!("fan{}_input", self.id))
+        self.hwmon.parse_file(format!("fan{}_input", self.id))
     }
 
     pub fn min(&self) -> Result<u32> {
-        self.hwmon.parse_file(&format!("fan{}_min", self.id))
+        self.hwmon.parse_file(format!("fan{}_min", self.id))
+    }
+
+    pub fn set_min(&self, rpm: u32) -> Result<()> {
+        self.hwmon
+            .write_file(format!("fan{}_min", self.id), rpm.to_string())
     }
 
     pub fn max(&self) -> Result<u32> {
-        self.hwmon.parse_file(&format!("fan{}_max", self.id))
+        self.hwmon.parse_file(format!("fan{}_max", self.id))
     }
 
     pub fn target(&self) -> Result<u32> {
-        self.hwmon.parse_file(&format!("fan{}_target", self.id))
+        self.hwmon.parse_file(format!("fan{}_target", self.id))
+    }
+
+    pub fn set_target(&self, rpm: u32) -> Result<()> {
+        self.hwmon
+            .write_file(format!("fan{}_target", self.id), rpm.to_string())
     }
 
     pub fn div(&self) -> Result<u8> {
-        self.hwmon.parse_file(&format!("fan{}_div", self.id))
+        self.hwmon.parse_file(format!("fan{}_div", self.id))
     }
 
     pub fn pulses(&self) -> Result<u8> {
-        self.hwmon.parse_file(&format!("fan{}_pulses", self.id))
+        self.hwmon.parse_file(format!("fan{}_pulses", self.id))
+    }
+
+    /// Applies the `compute` statement of the sensors config of the device, if any.
+    fn computed(&self, value: Rpm) -> Result<Rpm> {
+        self.hwmon
+            .compute("fan", self.id, f64::from(value.0))
+            .map(|value| Rpm(value.round() as u32))
+    }
+
+    pub fn input_rpm(&self) -> Result<Rpm> {
+        self.input().map(Rpm).and_then(|value| self.computed(value))
+    }
+
+    pub fn min_rpm(&self) -> Result<Rpm> {
+        self.min().map(Rpm).and_then(|value| self.computed(value))
+    }
+
+    pub fn max_rpm(&self) -> Result<Rpm> {
+        self.max().map(Rpm).and_then(|value| self.computed(value))
+    }
+
+    pub fn target_rpm(&self) -> Result<Rpm> {
+        self.target()
+            .map(Rpm)
+            .and_then(|value| self.computed(value))
+    }
+}
+
+impl<'a> HwMonAlarms for HwMonFan<'a> {
+    fn item_attribute(&self, item: &str) -> (&HwMon, String) {
+        (self.hwmon, format!("fan{}_{}", self.id, item))
     }
 }
@@ -0,0 +1,774 @@
+use crate::{HwMon, HwMonPwm, HwMonPwmEnable, HwMonSensor, HwMonTemp, SysClass};
+use std::cell::Cell;
+use std::io::{Error, ErrorKind, Result};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Source of time for a `FanController`, which can be replaced to simulate time passing.
+pub trait Clock {
+    fn now(&self) -> Instant;
+
+    fn sleep(&self, duration: Duration);
+}
+
+impl<C: Clock + ?Sized> Clock for &C {
+    fn now(&self) -> Instant {
+        (**self).now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        (**self).sleep(duration)
+    }
+}
+
+/// The monotonic clock of the system.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration)
+    }
+}
+
+/// A clock which only moves when it is advanced, or slept on.
+#[derive(Clone, Debug)]
+pub struct ManualClock {
+    now: Cell<Instant>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self {
+            now: Cell::new(Instant::now()),
+        }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration);
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.now.get()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.advance(duration)
+    }
+}
+
+/// A piecewise-linear mapping from temperature in degrees Celsius to PWM duty cycle.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FanCurve {
+    points: Vec<(f64, u8)>,
+}
+
+impl FanCurve {
+    /// Creates a curve from `(temperature, duty)` points, in any order. Below the first
+    /// point and above the last, the duty of the nearest point is used.
+    pub fn new(mut points: Vec<(f64, u8)>) -> Result<Self> {
+        if points.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "fan curve has no points",
+            ));
+        }
+
+        if points.iter().any(|&(temp, _)| !temp.is_finite()) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "fan curve has a temperature which is not finite",
+            ));
+        }
+
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        if points.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "fan curve has two points at the same temperature",
+            ));
+        }
+
+        Ok(Self { points })
+    }
+
+    pub fn points(&self) -> &[(f64, u8)] {
+        &self.points
+    }
+
+    pub fn duty(&self, temp: f64) -> u8 {
+        let first = self.points[0];
+        let last = self.points[self.points.len() - 1];
+        if temp <= first.0 {
+            return first.1;
+        }
+        if temp >= last.0 {
+            return last.1;
+        }
+
+        let upper = self
+            .points
+            .iter()
+            .position(|&(point, _)| point > temp)
+            .unwrap_or(self.points.len() - 1);
+        let (t0, d0) = self.points[upper - 1];
+        let (t1, d1) = self.points[upper];
+        let duty = f64::from(d0) + (temp - t0) / (t1 - t0) * (f64::from(d1) - f64::from(d0));
+        duty.round() as u8
+    }
+}
+
+/// Tuning of a `FanController`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FanControlOptions {
+    /// Degrees Celsius the temperature must fall before the duty cycle is lowered.
+    pub hysteresis: f64,
+    /// Lowest duty cycle to run the fan at. A duty cycle of 0 still stops the fan.
+    pub min_duty: u8,
+    /// Duty cycle to start a stopped fan with, for `spin_up_time`.
+    pub spin_up_duty: u8,
+    pub spin_up_time: Duration,
+    /// Weight of each new reading in the smoothed temperature, from 0 to 1. A weight of 1
+    /// disables smoothing.
+    pub smoothing: f64,
+}
+
+impl Default for FanControlOptions {
+    fn default() -> Self {
+        Self {
+            hysteresis: 0.0,
+            min_duty: 0,
+            spin_up_duty: 0,
+            spin_up_time: Duration::from_secs(0),
+            smoothing: 1.0,
+        }
+    }
+}
+
+impl FanControlOptions {
+    fn validate(&self) -> Result<()> {
+        if !(self.hysteresis.is_finite() && self.hysteresis >= 0.0) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "fan control hysteresis {} is not 0 or more",
+                    self.hysteresis
+                ),
+            ));
+        }
+
+        if !(self.smoothing > 0.0 && self.smoothing <= 1.0) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "fan control smoothing {} is not above 0 and at most 1",
+                    self.smoothing
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Drives a PWM output from the hottest of one or more temperature inputs.
+///
+/// The output is switched to manual control while the controller exists. When it is
+/// dropped, including when a panic unwinds past it, the output is switched back to the
+/// control method it had before, or to automatic control if it was already manual. If a
+/// temperature cannot be read, the fan is run at full speed and an error is returned.
+pub struct FanController<C: Clock = SystemClock> {
+    hwmon: HwMon,
+    pwm: u64,
+    pwm_items: Vec<String>,
+    temps: Vec<(HwMon, u64, Vec<String>)>,
+    curve: FanCurve,
+    options: FanControlOptions,
+    clock: C,
+    previous_enable: HwMonPwmEnable,
+    smoothed: Option<f64>,
+    curve_temp: Option<f64>,
+    duty: u8,
+    spin_up_until: Option<Instant>,
+    restored: bool,
+}
+
+impl FanController<SystemClock> {
+    pub fn new(
+        pwm: &HwMonPwm,
+        temps: &[HwMonTemp],
+        curve: FanCurve,
+        options: FanControlOptions,
+    ) -> Result<Self> {
+        Self::with_clock(pwm, temps, curve, options, SystemClock)
+    }
+}
+
+impl<C: Clock> FanController<C> {
+    /// Takes control of the output. Options which are out of range, such as a `smoothing`
+    /// of 0 or a negative `hysteresis`, are an error, and leave the output untouched.
+    pub fn with_clock(
+        pwm: &HwMonPwm,
+        temps: &[HwMonTemp],
+        curve: FanCurve,
+        options: FanControlOptions,
+        clock: C,
+    ) -> Result<Self> {
+        if temps.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "fan controller has no temperature inputs",
+            ));
+        }
+
+        options.validate()?;
+
+        let previous_enable = pwm.enable()?;
+        let duty = pwm.input()?;
+        pwm.set_enable(HwMonPwmEnable::Manual)?;
+
+        Ok(Self {
+            hwmon: pwm.hwmon().clone(),
+            pwm: pwm.id(),
+            pwm_items: pwm.sensor().items().to_vec(),
+            temps: temps
+                .iter()
+                .map(|temp| {
+                    let items = temp.sensor().items().to_vec();
+                    (temp.hwmon().clone(), temp.id(), items)
+                })
+                .collect(),
+            curve,
+            options,
+            clock,
+            previous_enable,
+            smoothed: None,
+            curve_temp: None,
+            duty,
+            spin_up_until: None,
+            restored: false,
+        })
+    }
+
+    fn pwm(&self) -> HwMonPwm<'_> {
+        let items = self.pwm_items.clone();
+        HwMonPwm::from_sensor(HwMonSensor::from_parts(&self.hwmon, "pwm", self.pwm, items))
+    }
+
+    /// The hottest of the temperature inputs, in degrees Celsius. A reading which is not
+    /// finite, such as from a `compute` statement which divides by zero, is an error.
+    fn read_temp(&self) -> Result<f64> {
+        let mut hottest = f64::NEG_INFINITY;
+        for (hwmon, id, items) in &self.temps {
+            let sensor = HwMonSensor::from_parts(hwmon, "temp", *id, items.clone());
+            let temp = HwMonTemp::from_sensor(sensor).input_celsius()?.0;
+            if !temp.is_finite() {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("{}: temp{} reads {}", hwmon.id(), id, temp),
+                ));
+            }
+
+            hottest = hottest.max(temp);
+        }
+
+        Ok(hottest)
+    }
+
+    /// Blends a reading into the smoothed temperature, which must stay finite.
+    fn smooth(&self, temp: f64) -> Result<f64> {
+        let smoothed = match self.smoothed {
+            Some(previous) => previous + self.options.smoothing * (temp - previous),
+            None => temp,
+        };
+
+        if !smoothed.is_finite() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("{}: smoothed temperature is {}", self.hwmon.id(), smoothed),
+            ));
+        }
+
+        Ok(smoothed)
+    }
+
+    /// The duty cycle which was last written to the output.
+    pub fn duty(&self) -> u8 {
+        self.duty
+    }
+
+    /// The smoothed temperature which the duty cycle was last computed from.
+    pub fn temperature(&self) -> Option<f64> {
+        self.smoothed
+    }
+
+    /// Reads the temperatures and sets the duty cycle of the output once.
+    pub fn update(&mut self) -> Result<u8> {
+        let smoothed = match self.read_temp().and_then(|temp| self.smooth(temp)) {
+            Ok(smoothed) => smoothed,
+            Err(why) => {
+                // Failing to full speed is safer than keeping a stale duty cycle.
+                let _ = self.pwm().set_input(u8::MAX);
+                self.duty = u8::MAX;
+                return Err(why);
+            }
+        };
+        self.smoothed = Some(smoothed);
+
+        let curve_temp = match self.curve_temp {
+            Some(previous)
+                if smoothed < previous && previous - smoothed < self.options.hysteresis =>
+            {
+                previous
+            }
+            _ => smoothed,
+        };
+        self.curve_temp = Some(curve_temp);
+
+        let mut duty = self.curve.duty(curve_temp);
+        if duty > 0 {
+            duty = duty.max(self.options.min_duty);
+        }
+
+        let now = self.clock.now();
+        if duty == 0 {
+            self.spin_up_until = None;
+        } else if self.duty == 0 && self.options.spin_up_duty > duty {
+            self.spin_up_until = Some(now + self.options.spin_up_time);
+        }
+
+        if let Some(until) = self.spin_up_until {
+            if now < until {
+                duty = duty.max(self.options.spin_up_duty);
+            } else {
+                self.spin_up_until = None;
+            }
+        }
+
+        self.pwm().set_input(duty)?;
+        self.duty = duty;
+        Ok(duty)
+    }
+
+    /// Updates the output every `interval` until `running` is cleared, or an update fails.
+    pub fn run(&mut self, interval: Duration, running: &AtomicBool) -> Result<()> {
+        while running.load(Ordering::SeqCst) {
+            self.update()?;
+            self.clock.sleep(interval);
+        }
+
+        Ok(())
+    }
+
+    /// Hands control of the output back, reporting any failure to do so.
+    pub fn restore(mut self) -> Result<()> {
+        self.restore_ref()
+    }
+
+    fn restore_ref(&mut self) -> Result<()> {
+        if self.restored {
+            return Ok(());
+        }
+
+        let pwm = self.pwm();
+        let previous = self.previous_enable;
+        let mut result = Err(Error::other("no control method to restore"));
+        for enable in [previous, HwMonPwmEnable::Automatic, HwMonPwmEnable::Off] {
+            if enable == HwMonPwmEnable::Manual {
+                continue;
+            }
+
+            result = pwm.set_enable(enable);
+            if result.is_ok() {
+                break;
+            }
+        }
+
+        self.restored = result.is_ok();
+        result
+    }
+}
+
+impl<C: Clock> Drop for FanController<C> {
+    fn drop(&mut self) {
+        let _ = self.restore_ref();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use crate::SensorsConfig;
+    use std::fs;
+    use std::panic::{self, AssertUnwindSafe};
+
+    /// A hwmon device with two temperature inputs and one PWM output, which is under
+    /// automatic control with a duty cycle of 0.
+    struct Fixture {
+        dir: TempDir,
+        hwmon: HwMon,
+    }
+
+    impl Fixture {
+        fn new(name: &str) -> Self {
+            let dir = TempDir::new(&format!("fan-control-{}", name));
+            for (file, value) in &[
+                ("name", "nct6775"),
+                ("temp1_input", "40000"),
+                ("temp2_input", "30000"),
+                ("pwm1", "0"),
+                ("pwm1_enable", "2"),
+            ] {
+                fs::write(dir.path().join(file), value).unwrap();
+            }
+
+            let hwmon = unsafe { HwMon::from_path_unchecked(dir.path().to_owned()) };
+            Fixture { dir, hwmon }
+        }
+
+        fn set_temp(&self, id: u64, celsius: f64) {
+            let millidegrees = (celsius * 1000.0).round() as i64;
+            fs::write(
+                self.dir.path().join(format!("temp{}_input", id)),
+                millidegrees.to_string(),
+            )
+            .unwrap();
+        }
+
+        /// Takes the duty cycle which was last written. Unlike sysfs attributes, regular
+        /// files keep the tail of longer values, so the file is emptied after reading.
+        fn take_duty(&self) -> u8 {
+            let path = self.dir.path().join("pwm1");
+            let duty = fs::read_to_string(&path).unwrap().trim().parse().unwrap();
+            fs::write(&path, "").unwrap();
+            duty
+        }
+
+        fn enable(&self) -> u8 {
+            let path = self.dir.path().join("pwm1_enable");
+            fs::read_to_string(path).unwrap().trim().parse().unwrap()
+        }
+
+        fn controller<'a>(
+            &self,
+            curve: &[(f64, u8)],
+            options: FanControlOptions,
+            clock: &'a ManualClock,
+        ) -> FanController<&'a ManualClock> {
+            let pwm = HwMonPwm::new(&self.hwmon, 1).unwrap();
+            let temps = [
+                HwMonTemp::new(&self.hwmon, 1).unwrap(),
+                HwMonTemp::new(&self.hwmon, 2).unwrap(),
+            ];
+            let curve = FanCurve::new(curve.to_vec()).unwrap();
+            let controller = FanController::with_clock(&pwm, &temps, curve, options, clock);
+            let controller = controller.unwrap();
+            fs::write(self.dir.path().join("pwm1"), "").unwrap();
+            controller
+        }
+    }
+
+    const CURVE: &[(f64, u8)] = &[(40.0, 55), (70.0, 255)];
+
+    #[test]
+    fn curve_interpolation() {
+        let curve = FanCurve::new(vec![(70.0, 255), (40.0, 55)]).unwrap();
+        assert_eq!(curve.points(), &[(40.0, 55), (70.0, 255)]);
+        assert_eq!(curve.duty(-10.0), 55);
+        assert_eq!(curve.duty(40.0), 55);
+        assert_eq!(curve.duty(55.0), 155);
+        assert_eq!(curve.duty(61.0), 195);
+        assert_eq!(curve.duty(70.0), 255);
+        assert_eq!(curve.duty(120.0), 255);
+
+        let curve = FanCurve::new(vec![(30.0, 0), (50.0, 100), (60.0, 80)]).unwrap();
+        assert_eq!(curve.duty(40.0), 50);
+        assert_eq!(curve.duty(55.0), 90);
+
+        assert!(FanCurve::new(Vec::new()).is_err());
+        assert!(FanCurve::new(vec![(f64::NAN, 100)]).is_err());
+        assert!(FanCurve::new(vec![(40.0, 100), (40.0, 200)]).is_err());
+    }
+
+    #[test]
+    fn follows_hottest_input() {
+        let fixture = Fixture::new("hottest");
+        let clock = ManualClock::new();
+        let mut controller = fixture.controller(CURVE, FanControlOptions::default(), &clock);
+        assert_eq!(fixture.enable(), u8::from(HwMonPwmEnable::Manual));
+
+        fixture.set_temp(1, 55.0);
+        assert_eq!(controller.update().unwrap(), 155);
+        assert_eq!(fixture.take_duty(), 155);
+        assert_eq!(controller.temperature(), Some(55.0));
+
+        fixture.set_temp(2, 61.0);
+        assert_eq!(controller.update().unwrap(), 195);
+        assert_eq!(fixture.take_duty(), 195);
+        assert_eq!(controller.duty(), 195);
+    }
+
+    #[test]
+    fn hysteresis() {
+        let fixture = Fixture::new("hysteresis");
+        let clock = ManualClock::new();
+        let options = FanControlOptions {
+            hysteresis: 5.0,
+            ..FanControlOptions::default()
+        };
+        let mut controller = fixture.controller(CURVE, options, &clock);
+
+        fixture.set_temp(1, 61.0);
+        assert_eq!(controller.update().unwrap(), 195);
+
+        // Small drops keep the duty cycle.
+        fixture.set_temp(1, 57.0);
+        assert_eq!(controller.update().unwrap(), 195);
+        fixture.set_temp(1, 56.5);
+        assert_eq!(controller.update().unwrap(), 195);
+
+        // A drop of the full hysteresis lowers it.
+        fixture.set_temp(1, 55.0);
+        assert_eq!(controller.update().unwrap(), 155);
+
+        // Rises take effect at once.
+        fixture.set_temp(1, 56.5);
+        assert_eq!(controller.update().unwrap(), 165);
+    }
+
+    #[test]
+    fn min_duty() {
+        let fixture = Fixture::new("min-duty");
+        let clock = ManualClock::new();
+        let options = FanControlOptions {
+            min_duty: 80,
+            ..FanControlOptions::default()
+        };
+        let curve = &[(40.0, 0), (50.0, 20), (70.0, 255)];
+        let mut controller = fixture.controller(curve, options, &clock);
+
+        assert_eq!(controller.update().unwrap(), 0);
+        fixture.set_temp(1, 45.0);
+        assert_eq!(controller.update().unwrap(), 80);
+    }
+
+    #[test]
+    fn spin_up() {
+        let fixture = Fixture::new("spin-up");
+        let clock = ManualClock::new();
+        let options = FanControlOptions {
+            spin_up_duty: 200,
+            spin_up_time: Duration::from_secs(2),
+            ..FanControlOptions::default()
+        };
+        let curve = &[(40.0, 0), (50.0, 100), (70.0, 255)];
+        let mut controller = fixture.controller(curve, options, &clock);
+
+        // The fan starts out stopped, so starting it takes the spin-up duty.
+        fixture.set_temp(1, 45.0);
+        assert_eq!(controller.update().unwrap(), 200);
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(controller.update().unwrap(), 200);
+
+        // Once the spin-up time expires, the curve takes over.
+        clock.advance(Duration::from_millis(1500));
+        assert_eq!(controller.update().unwrap(), 50);
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(controller.update().unwrap(), 50);
+
+        // Stopping and starting again spins up again.
+        fixture.set_temp(1, 35.0);
+        assert_eq!(controller.update().unwrap(), 0);
+        fixture.set_temp(1, 45.0);
+        assert_eq!(controller.update().unwrap(), 200);
+        assert_eq!(fixture.take_duty(), 200);
+    }
+
+    #[test]
+    fn smoothing() {
+        let fixture = Fixture::new("smoothing");
+        let clock = ManualClock::new();
+        let options = FanControlOptions {
+            smoothing: 0.5,
+            ..FanControlOptions::default()
+        };
+        let mut controller = fixture.controller(CURVE, options, &clock);
+
+        // The first reading is taken as is.
+        assert_eq!(controller.update().unwrap(), 55);
+        assert_eq!(controller.temperature(), Some(40.0));
+
+        fixture.set_temp(1, 70.0);
+        assert_eq!(controller.update().unwrap(), 155);
+        assert_eq!(controller.temperature(), Some(55.0));
+        assert_eq!(controller.update().unwrap(), 205);
+        assert_eq!(controller.temperature(), Some(62.5));
+    }
+
+    #[test]
+    fn full_speed_on_read_error() {
+        let fixture = Fixture::new("read-error");
+        let clock = ManualClock::new();
+        let mut controller = fixture.controller(CURVE, FanControlOptions::default(), &clock);
+        assert_eq!(controller.update().unwrap(), 55);
+        assert_eq!(fixture.take_duty(), 55);
+
+        fs::remove_file(fixture.dir.path().join("temp2_input")).unwrap();
+        assert!(controller.update().is_err());
+        assert_eq!(fixture.take_duty(), 255);
+        assert_eq!(controller.duty(), 255);
+    }
+
+    #[test]
+    fn full_speed_on_non_finite_reading() {
+        for (name, compute) in &[("nan", "(@-@)/0, @"), ("infinite", "@/0, @")] {
+            let fixture = Fixture::new(name);
+            let config = format!("chip \"nct6775-*\"\ncompute temp1 {}\n", compute);
+            let config = SensorsConfig::parse(&config).unwrap();
+            let hwmon = fixture.hwmon.clone().with_sensors_config(&config).unwrap();
+
+            let clock = ManualClock::new();
+            let pwm = HwMonPwm::new(&hwmon, 1).unwrap();
+            let temps = [HwMonTemp::new(&hwmon, 1).unwrap()];
+            let curve = FanCurve::new(CURVE.to_vec()).unwrap();
+            let mut controller = FanController::with_clock(
+                &pwm,
+                &temps,
+                curve,
+                FanControlOptions::default(),
+                &clock,
+            )
+            .unwrap();
+            fs::write(fixture.dir.path().join("pwm1"), "").unwrap();
+
+            let err = controller.update().unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidData, "{}", name);
+            assert_eq!(fixture.take_duty(), 255);
+        }
+    }
+
+    #[test]
+    fn full_speed_on_non_finite_smoothing() {
+        let fixture = Fixture::new("smoothing-overflow");
+        let config = SensorsConfig::parse("chip \"nct6775-*\"\ncompute temp1 @*^707, @\n").unwrap();
+        let hwmon = fixture.hwmon.clone().with_sensors_config(&config).unwrap();
+        fixture.set_temp(1, 10.0);
+
+        let clock = ManualClock::new();
+        let pwm = HwMonPwm::new(&hwmon, 1).unwrap();
+        let temps = [HwMonTemp::new(&hwmon, 1).unwrap()];
+        let curve = FanCurve::new(CURVE.to_vec()).unwrap();
+        let options = FanControlOptions {
+            smoothing: 0.5,
+            ..FanControlOptions::default()
+        };
+        let mut controller =
+            FanController::with_clock(&pwm, &temps, curve, options, &clock).unwrap();
+        assert_eq!(controller.update().unwrap(), 255);
+        fs::write(fixture.dir.path().join("pwm1"), "").unwrap();
+
+        // Both readings are finite, but the step between them is not.
+        fixture.set_temp(1, -10.0);
+        let err = controller.update().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(fixture.take_duty(), 255);
+    }
+
+    #[test]
+    fn invalid_options() {
+        let fixture = Fixture::new("invalid-options");
+        let pwm = HwMonPwm::new(&fixture.hwmon, 1).unwrap();
+        let temps = [HwMonTemp::new(&fixture.hwmon, 1).unwrap()];
+        let curve = FanCurve::new(CURVE.to_vec()).unwrap();
+
+        let invalid = [
+            (f64::NAN, 1.0),
+            (f64::INFINITY, 1.0),
+            (-1.0, 1.0),
+            (0.0, 0.0),
+            (0.0, -0.5),
+            (0.0, 1.5),
+            (0.0, f64::NAN),
+            (0.0, f64::INFINITY),
+        ];
+        for &(hysteresis, smoothing) in &invalid {
+            let options = FanControlOptions {
+                hysteresis,
+                smoothing,
+                ..FanControlOptions::default()
+            };
+            let err = FanController::new(&pwm, &temps, curve.clone(), options).err();
+            let kind = err.map(|err| err.kind());
+            assert_eq!(
+                kind,
+                Some(ErrorKind::InvalidInput),
+                "{} {}",
+                hysteresis,
+                smoothing
+            );
+            assert_eq!(fixture.enable(), 2);
+        }
+
+        let options = FanControlOptions {
+            hysteresis: 0.0,
+            smoothing: 0.01,
+            ..FanControlOptions::default()
+        };
+        assert!(FanController::new(&pwm, &temps, curve, options).is_ok());
+    }
+
+    #[test]
+    fn restore_on_drop() {
+        // The previous method is restored, or automatic control if it was manual.
+        for &(previous, restored) in &[(2, 2), (5, 5), (0, 0), (1, 2)] {
+            let fixture = Fixture::new(&format!("restore-{}", previous));
+            fs::write(fixture.dir.path().join("pwm1_enable"), previous.to_string()).unwrap();
+
+            let clock = ManualClock::new();
+            let mut controller = fixture.controller(CURVE, FanControlOptions::default(), &clock);
+            controller.update().unwrap();
+            assert_eq!(fixture.enable(), 1);
+
+            drop(controller);
+            assert_eq!(fixture.enable(), restored, "previous {}", previous);
+        }
+    }
+
+    #[test]
+    fn restore_explicitly() {
+        let fixture = Fixture::new("restore-explicitly");
+        let clock = ManualClock::new();
+        let controller = fixture.controller(CURVE, FanControlOptions::default(), &clock);
+        controller.restore().unwrap();
+        assert_eq!(fixture.enable(), 2);
+    }
+
+    #[test]
+    fn restore_on_panic() {
+        let fixture = Fixture::new("restore-panic");
+        let clock = ManualClock::new();
+        let mut controller = fixture.controller(CURVE, FanControlOptions::default(), &clock);
+
+        let result = panic::catch_unwind(AssertUnwindSafe(move || {
+            controller.update().unwrap();
+            panic!("controller loop failed");
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(fixture.enable(), 2);
+    }
+}
@@ -0,0 +1,20 @@
+use crate::{HwMon, HwMonAlarms, HwMonSensor};
+use std::io::Result;
+
+hwmon_sensor!(
+    /// A humidity sensor, `humidityN`, with values in milli-percent.
+    HwMonHumidity, "humidity", |s| s.input()
+);
+
+impl<'a> HwMonHumidity<'a> {
+    hwmon_items! {
+        /// Relative humidity, in milli-percent.
+        input -> u32;
+        min -> u32;
+        max -> u32;
+        min_hyst -> u32;
+        max_hyst -> u32;
+        rated_min -> u32;
+        rated_max -> u32;
+    }
+}
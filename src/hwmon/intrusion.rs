@@ -0,0 +1,19 @@
+use crate::{HwMon, HwMonAlarms, HwMonSensor, SysClass};
+use std::io::Result;
+
+hwmon_sensor!(
+    /// A chassis intrusion detector, `intrusionN`.
+    HwMonIntrusion, "intrusion", |s| s.alarm()
+);
+
+impl<'a> HwMonIntrusion<'a> {
+    hwmon_items! {
+        /// Whether the chassis has been opened since the alarm was cleared.
+        alarm -> u8;
+    }
+
+    /// Clears the alarm, once the chassis has been closed.
+    pub fn clear_alarm(&self) -> Result<()> {
+        self.hwmon().write_file(self.sensor.attribute("alarm"), "0")
+    }
+}
@@ -1,20 +1,143 @@
 use crate::SysClass;
 use std::io::Result;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Defines a typed sensor of one type, such as `in`, over the `HwMonSensor` it was
+/// discovered as. `$check` is the read which `new` requires to succeed.
+macro_rules! hwmon_sensor {
+    ($(#[$attr:meta])* $name:ident, $kind:literal, |$s:ident| $check:expr) => {
+        $(#[$attr])*
+        pub struct $name<'a> {
+            sensor: HwMonSensor<'a>,
+        }
+
+        impl<'a> $name<'a> {
+            pub fn new(hwmon: &'a HwMon, id: u64) -> Result<Self> {
+                let $s = Self::from_sensor(hwmon.sensor($kind, id)?);
+
+                $check?;
+
+                Ok($s)
+            }
+
+            pub(crate) fn from_sensor(sensor: HwMonSensor<'a>) -> Self {
+                Self { sensor }
+            }
+
+            pub fn hwmon(&self) -> &'a HwMon {
+                self.sensor.hwmon()
+            }
+
+            pub fn id(&self) -> u64 {
+                self.sensor.id()
+            }
+
+            /// The sensor with every item it supports, as discovered when this was created.
+            pub fn sensor(&self) -> &HwMonSensor<'a> {
+                &self.sensor
+            }
+
+            /// The label from the sensors config of the device, or else from the chip.
+            pub fn label(&self) -> Result<String> {
+                self.sensor.label()
+            }
+        }
+
+        impl<'a> HwMonAlarms for $name<'a> {
+            fn item_attribute(&self, item: &str) -> (&HwMon, String) {
+                (self.sensor.hwmon(), self.sensor.attribute(item))
+            }
+        }
+    };
+}
+
+/// Defines getters for items of a typed sensor, in the raw units of sysfs.
+macro_rules! hwmon_items {
+    ($($(#[$attr:meta])* $item:ident -> $ty:ty;)*) => {
+        $(
+            $(#[$attr])*
+            pub fn $item(&self) -> Result<$ty> {
+                self.sensor.parse(stringify!($item))
+            }
+        )*
+    };
+}
+
+/// Defines getters which convert raw items to `$unit` and apply the `compute` statement of
+/// the sensors config of the device, if any.
+macro_rules! hwmon_scaled {
+    ($unit:ident::$from:ident; $($item:ident => $scaled:ident;)*) => {
+        fn computed(&self, value: $unit) -> Result<$unit> {
+            let sensor = &self.sensor;
+            sensor
+                .hwmon()
+                .compute(sensor.kind(), sensor.id(), value.0)
+                .map($unit)
+        }
+
+        $(
+            pub fn $scaled(&self) -> Result<$unit> {
+                self.$item()
+                    .map($unit::$from)
+                    .and_then(|value| self.computed(value))
+            }
+        )*
+    };
+}
+
+pub use self::alarms::{HwMonActiveAlarms, HwMonAlarm, HwMonAlarms};
+mod alarms;
+
+pub use self::current::HwMonCurrent;
+mod current;
+
+pub use self::energy::HwMonEnergy;
+mod energy;
+
+pub use self::fan_control::{
+    Clock, FanControlOptions, FanController, FanCurve, ManualClock, SystemClock,
+};
+mod fan_control;
 
 pub use self::fan::HwMonFan;
 mod fan;
 
-pub use self::pwm::HwMonPwm;
+pub use self::humidity::HwMonHumidity;
+mod humidity;
+
+pub use self::intrusion::HwMonIntrusion;
+mod intrusion;
+
+pub use self::power::HwMonPower;
+mod power;
+
+pub use self::pwm::{HwMonPwm, HwMonPwmAutoPoint, HwMonPwmEnable, HwMonPwmMode};
 mod pwm;
 
+pub use self::sensor::HwMonSensor;
+mod sensor;
+
+pub use self::sensors_config::SensorsConfig;
+mod sensors_config;
+
+pub use self::stable_id::HwMonStableId;
+mod stable_id;
+
 pub use self::temp::HwMonTemp;
 mod temp;
 
+pub use self::units::{Amps, Celsius, Joules, Rpm, Volts, Watts};
+mod units;
+
+pub use self::voltage::HwMonVoltage;
+mod voltage;
+
 /// A hardware monitoring device in /sys/class/hwmon
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct HwMon {
     path: PathBuf,
+    config: Option<Arc<sensors_config::SensorsChip>>,
 }
 
 impl SysClass for HwMon {
@@ -23,7 +146,7 @@ impl SysClass for HwMon {
     }
 
     unsafe fn from_path_unchecked(path: PathBuf) -> Self {
-        Self { path }
+        Self { path, config: None }
     }
 
     fn path(&self) -> &Path {
@@ -40,11 +163,188 @@ impl HwMon {
         HwMonFan::new(self, id)
     }
 
+    pub fn fans(&self) -> Result<Vec<HwMonFan<'_>>> {
+        Ok(self
+            .sensors_of("fan")?
+            .into_iter()
+            .map(HwMonFan::from_sensor)
+            .collect())
+    }
+
     pub fn pwm<'a>(&'a self, id: u64) -> Result<HwMonPwm<'a>> {
         HwMonPwm::new(self, id)
     }
 
+    pub fn pwms(&self) -> Result<Vec<HwMonPwm<'_>>> {
+        Ok(self
+            .sensors_of("pwm")?
+            .into_iter()
+            .map(HwMonPwm::from_sensor)
+            .collect())
+    }
+
     pub fn temp<'a>(&'a self, id: u64) -> Result<HwMonTemp<'a>> {
         HwMonTemp::new(self, id)
     }
+
+    pub fn temps(&self) -> Result<Vec<HwMonTemp<'_>>> {
+        Ok(self
+            .sensors_of("temp")?
+            .into_iter()
+            .map(HwMonTemp::from_sensor)
+            .collect())
+    }
+
+    pub fn current<'a>(&'a self, id: u64) -> Result<HwMonCurrent<'a>> {
+        HwMonCurrent::new(self, id)
+    }
+
+    pub fn currents(&self) -> Result<Vec<HwMonCurrent<'_>>> {
+        Ok(self
+            .sensors_of("curr")?
+            .into_iter()
+            .map(HwMonCurrent::from_sensor)
+            .collect())
+    }
+
+    pub fn energy<'a>(&'a self, id: u64) -> Result<HwMonEnergy<'a>> {
+        HwMonEnergy::new(self, id)
+    }
+
+    pub fn energies(&self) -> Result<Vec<HwMonEnergy<'_>>> {
+        Ok(self
+            .sensors_of("energy")?
+            .into_iter()
+            .map(HwMonEnergy::from_sensor)
+            .collect())
+    }
+
+    pub fn humidity<'a>(&'a self, id: u64) -> Result<HwMonHumidity<'a>> {
+        HwMonHumidity::new(self, id)
+    }
+
+    pub fn humidities(&self) -> Result<Vec<HwMonHumidity<'_>>> {
+        Ok(self
+            .sensors_of("humidity")?
+            .into_iter()
+            .map(HwMonHumidity::from_sensor)
+            .collect())
+    }
+
+    pub fn intrusion<'a>(&'a self, id: u64) -> Result<HwMonIntrusion<'a>> {
+        HwMonIntrusion::new(self, id)
+    }
+
+    pub fn intrusions(&self) -> Result<Vec<HwMonIntrusion<'_>>> {
+        Ok(self
+            .sensors_of("intrusion")?
+            .into_iter()
+            .map(HwMonIntrusion::from_sensor)
+            .collect())
+    }
+
+    pub fn power<'a>(&'a self, id: u64) -> Result<HwMonPower<'a>> {
+        HwMonPower::new(self, id)
+    }
+
+    pub fn powers(&self) -> Result<Vec<HwMonPower<'_>>> {
+        Ok(self
+            .sensors_of("power")?
+            .into_iter()
+            .map(HwMonPower::from_sensor)
+            .collect())
+    }
+
+    pub fn voltage<'a>(&'a self, id: u64) -> Result<HwMonVoltage<'a>> {
+        HwMonVoltage::new(self, id)
+    }
+
+    pub fn voltages(&self) -> Result<Vec<HwMonVoltage<'_>>> {
+        Ok(self
+            .sensors_of("in")?
+            .into_iter()
+            .map(HwMonVoltage::from_sensor)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use std::fs;
+
+    fn fixture(dir: &TempDir) -> HwMon {
+        for (file, value) in &[
+            ("in0_input", "1200"),
+            ("in0_max", "-500"),
+            ("curr1_input", "2500"),
+            ("curr1_crit", "-1250"),
+            ("power1_average", "12500000"),
+            ("power1_average_interval", "1000"),
+            ("power1_accuracy", "5.0%"),
+            ("power1_cap", "95000000"),
+            ("energy1_input", "3600000000"),
+            ("humidity1_input", "45500"),
+            ("humidity1_max_hyst", "60000"),
+            ("intrusion0_alarm", "1"),
+        ] {
+            fs::write(dir.path().join(file), format!("{}\n", value)).unwrap();
+        }
+
+        unsafe { HwMon::from_path_unchecked(dir.path().to_path_buf()) }
+    }
+
+    #[test]
+    fn sensor_scaling() {
+        let dir = TempDir::new("hwmon-scaling");
+        let hwmon = fixture(&dir);
+
+        let voltage = hwmon.voltage(0).unwrap();
+        assert_eq!(voltage.input().unwrap(), 1200);
+        assert_eq!(voltage.input_volts().unwrap(), Volts(1.2));
+        assert_eq!(voltage.max_volts().unwrap(), Volts(-0.5));
+
+        let current = hwmon.current(1).unwrap();
+        assert_eq!(current.input_amps().unwrap(), Amps(2.5));
+        assert_eq!(current.crit_amps().unwrap(), Amps(-1.25));
+
+        // Power sensors which only measure the average are found too.
+        let power = hwmon.power(1).unwrap();
+        assert!(power.input().is_err());
+        assert_eq!(power.average_watts().unwrap(), Watts(12.5));
+        assert_eq!(power.average_interval().unwrap(), 1000);
+        assert_eq!(power.accuracy().unwrap(), "5.0%");
+        assert_eq!(power.cap_watts().unwrap(), Watts(95.0));
+
+        let energy = hwmon.energy(1).unwrap();
+        assert_eq!(energy.input_joules().unwrap(), Joules(3600.0));
+        assert_eq!(energy.input_joules().unwrap().watt_hours(), 1.0);
+
+        let humidity = hwmon.humidity(1).unwrap();
+        assert_eq!(humidity.input().unwrap(), 45500);
+        assert_eq!(humidity.max_hyst().unwrap(), 60000);
+
+        let intrusion = hwmon.intrusion(0).unwrap();
+        assert_eq!(intrusion.alarm().unwrap(), 1);
+    }
+
+    #[test]
+    fn sensor_lists() {
+        let dir = TempDir::new("hwmon-lists");
+        let hwmon = fixture(&dir);
+
+        assert_eq!(hwmon.voltages().unwrap()[0].id(), 0);
+        assert_eq!(hwmon.currents().unwrap().len(), 1);
+        assert_eq!(hwmon.powers().unwrap()[0].sensor().items().len(), 4);
+        assert_eq!(hwmon.energies().unwrap().len(), 1);
+        assert_eq!(hwmon.humidities().unwrap().len(), 1);
+        assert_eq!(hwmon.intrusions().unwrap()[0].id(), 0);
+
+        // Sensors which are missing, or lack the item `new` checks, are not found.
+        assert!(hwmon.voltage(1).is_err());
+        fs::write(dir.path().join("energy2_label"), "PKG\n").unwrap();
+        assert!(hwmon.energy(2).is_err());
+        assert_eq!(hwmon.energies().unwrap()[1].label().unwrap(), "PKG");
+    }
 }
@@ -0,0 +1,67 @@
+use crate::{HwMon, HwMonAlarms, HwMonSensor, SysClass, Watts};
+use std::io::Result;
+
+hwmon_sensor!(
+    /// A power sensor, `powerN`, with values in microwatts. Some devices only measure
+    /// average power, so either `input` or `average` must be readable.
+    HwMonPower, "power", |s| s.input().or_else(|_| s.average())
+);
+
+impl<'a> HwMonPower<'a> {
+    hwmon_items! {
+        /// Instantaneous power use, in microwatts.
+        input -> u64;
+        input_highest -> u64;
+        input_lowest -> u64;
+        /// Average power use, in microwatts.
+        average -> u64;
+        /// Interval over which `average` is taken, in milliseconds.
+        average_interval -> u32;
+        average_highest -> u64;
+        average_lowest -> u64;
+        average_min -> u64;
+        average_max -> u64;
+        /// Accuracy of the measurement, such as `5.0%`.
+        accuracy -> String;
+        /// Power use which the device is limited to, in microwatts.
+        cap -> u64;
+        cap_hyst -> u64;
+        cap_min -> u64;
+        cap_max -> u64;
+        min -> u64;
+        max -> u64;
+        lcrit -> u64;
+        crit -> u64;
+        rated_min -> u64;
+        rated_max -> u64;
+    }
+
+    /// Resets the `input_highest`, `input_lowest`, `average_highest` and `average_lowest`
+    /// history.
+    pub fn reset_history(&self) -> Result<()> {
+        self.hwmon()
+            .write_file(self.sensor.attribute("reset_history"), "1")
+    }
+
+    hwmon_scaled! {
+        Watts::from_microwatts;
+        input => input_watts;
+        input_highest => input_highest_watts;
+        input_lowest => input_lowest_watts;
+        average => average_watts;
+        average_highest => average_highest_watts;
+        average_lowest => average_lowest_watts;
+        average_min => average_min_watts;
+        average_max => average_max_watts;
+        cap => cap_watts;
+        cap_hyst => cap_hyst_watts;
+        cap_min => cap_min_watts;
+        cap_max => cap_max_watts;
+        min => min_watts;
+        max => max_watts;
+        lcrit => lcrit_watts;
+        crit => crit_watts;
+        rated_min => rated_min_watts;
+        rated_max => rated_max_watts;
+    }
+}
@@ -1,41 +1,298 @@
-use crate::{HwMon, SysClass};
+use crate::{HwMon, HwMonSensor, SysClass};
 use std::io::Result;
 
+/// Fan speed control method of a PWM output, from `pwmN_enable`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HwMonPwmEnable {
+    /// No fan speed control, so the fan runs at full speed.
+    Off,
+    /// The duty cycle is set by writing to `pwmN`.
+    Manual,
+    /// The chip controls the fan speed, such as by its `auto_point` curve.
+    Automatic,
+    /// A driver specific method, with a value of 3 or more.
+    DriverSpecific(u8),
+}
+
+impl From<u8> for HwMonPwmEnable {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => HwMonPwmEnable::Off,
+            1 => HwMonPwmEnable::Manual,
+            2 => HwMonPwmEnable::Automatic,
+            other => HwMonPwmEnable::DriverSpecific(other),
+        }
+    }
+}
+
+impl From<HwMonPwmEnable> for u8 {
+    fn from(enable: HwMonPwmEnable) -> Self {
+        match enable {
+            HwMonPwmEnable::Off => 0,
+            HwMonPwmEnable::Manual => 1,
+            HwMonPwmEnable::Automatic => 2,
+            HwMonPwmEnable::DriverSpecific(value) => value,
+        }
+    }
+}
+
+/// Output mode of a PWM output, from `pwmN_mode`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HwMonPwmMode {
+    Dc,
+    Pwm,
+}
+
+impl From<u8> for HwMonPwmMode {
+    /// The ABI defines 0 as DC and 1 as PWM. Other values are treated as PWM.
+    fn from(value: u8) -> Self {
+        match value {
+            0 => HwMonPwmMode::Dc,
+            _ => HwMonPwmMode::Pwm,
+        }
+    }
+}
+
+impl From<HwMonPwmMode> for u8 {
+    fn from(mode: HwMonPwmMode) -> Self {
+        match mode {
+            HwMonPwmMode::Dc => 0,
+            HwMonPwmMode::Pwm => 1,
+        }
+    }
+}
+
+/// A point of the automatic fan speed curve of a PWM output.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct HwMonPwmAutoPoint {
+    pub point: u64,
+    pub pwm: Option<u8>,
+    /// Temperature in millidegrees Celsius.
+    pub temp: Option<i32>,
+}
+
 pub struct HwMonPwm<'a> {
     hwmon: &'a HwMon,
     id: u64,
+    sensor: HwMonSensor<'a>,
 }
 
 impl<'a> HwMonPwm<'a> {
     pub fn new(hwmon: &'a HwMon, id: u64) -> Result<Self> {
-        let s = Self { hwmon, id };
+        let s = Self::from_sensor(hwmon.sensor("pwm", id)?);
 
         s.input()?;
 
         Ok(s)
     }
 
+    pub(crate) fn from_sensor(sensor: HwMonSensor<'a>) -> Self {
+        Self {
+            hwmon: sensor.hwmon(),
+            id: sensor.id(),
+            sensor,
+        }
+    }
+
+    pub fn hwmon(&self) -> &'a HwMon {
+        self.hwmon
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// The sensor with every item it supports, as discovered when this was created.
+    pub fn sensor(&self) -> &HwMonSensor<'a> {
+        &self.sensor
+    }
+
     pub fn input(&self) -> Result<u8> {
-        self.hwmon.parse_file(&format!("pwm{}", self.id))
+        self.hwmon.parse_file(format!("pwm{}", self.id))
+    }
+
+    /// Sets the duty cycle, from 0 to 255. Only effective in `HwMonPwmEnable::Manual`.
+    pub fn set_input(&self, duty: u8) -> Result<()> {
+        self.hwmon
+            .write_file(format!("pwm{}", self.id), duty.to_string())
     }
 
     pub fn min(&self) -> Result<u8> {
-        self.hwmon.parse_file(&format!("pwm{}_min", self.id))
+        self.hwmon.parse_file(format!("pwm{}_min", self.id))
     }
 
     pub fn max(&self) -> Result<u8> {
-        self.hwmon.parse_file(&format!("pwm{}_max", self.id))
+        self.hwmon.parse_file(format!("pwm{}_max", self.id))
     }
 
     pub fn freq(&self) -> Result<u32> {
-        self.hwmon.parse_file(&format!("pwm{}_freq", self.id))
+        self.hwmon.parse_file(format!("pwm{}_freq", self.id))
     }
 
-    pub fn enable(&self) -> Result<u8> {
-        self.hwmon.parse_file(&format!("pwm{}_enable", self.id))
+    pub fn enable(&self) -> Result<HwMonPwmEnable> {
+        self.hwmon
+            .parse_file::<u8, _>(format!("pwm{}_enable", self.id))
+            .map(HwMonPwmEnable::from)
     }
 
-    pub fn mode(&self) -> Result<u8> {
-        self.hwmon.parse_file(&format!("pwm{}_mode", self.id))
+    pub fn set_enable(&self, enable: HwMonPwmEnable) -> Result<()> {
+        self.hwmon.write_file(
+            format!("pwm{}_enable", self.id),
+            u8::from(enable).to_string(),
+        )
+    }
+
+    pub fn mode(&self) -> Result<HwMonPwmMode> {
+        self.hwmon
+            .parse_file::<u8, _>(format!("pwm{}_mode", self.id))
+            .map(HwMonPwmMode::from)
+    }
+
+    pub fn set_mode(&self, mode: HwMonPwmMode) -> Result<()> {
+        self.hwmon
+            .write_file(format!("pwm{}_mode", self.id), u8::from(mode).to_string())
+    }
+
+    /// Bitmask of the temperature channels which drive the automatic curve.
+    pub fn auto_channels_temp(&self) -> Result<u32> {
+        self.hwmon
+            .parse_file(format!("pwm{}_auto_channels_temp", self.id))
+    }
+
+    pub fn set_auto_channels_temp(&self, channels: u32) -> Result<()> {
+        self.hwmon.write_file(
+            format!("pwm{}_auto_channels_temp", self.id),
+            channels.to_string(),
+        )
+    }
+
+    pub fn auto_point_pwm(&self, point: u64) -> Result<u8> {
+        self.hwmon
+            .parse_file(format!("pwm{}_auto_point{}_pwm", self.id, point))
+    }
+
+    pub fn set_auto_point_pwm(&self, point: u64, duty: u8) -> Result<()> {
+        self.hwmon.write_file(
+            format!("pwm{}_auto_point{}_pwm", self.id, point),
+            duty.to_string(),
+        )
+    }
+
+    /// Temperature of a curve point, in millidegrees Celsius.
+    pub fn auto_point_temp(&self, point: u64) -> Result<i32> {
+        self.hwmon
+            .parse_file(format!("pwm{}_auto_point{}_temp", self.id, point))
+    }
+
+    pub fn set_auto_point_temp(&self, point: u64, millidegrees: i32) -> Result<()> {
+        self.hwmon.write_file(
+            format!("pwm{}_auto_point{}_temp", self.id, point),
+            millidegrees.to_string(),
+        )
+    }
+
+    pub fn auto_point_temp_hyst(&self, point: u64) -> Result<i32> {
+        self.hwmon
+            .parse_file(format!("pwm{}_auto_point{}_temp_hyst", self.id, point))
+    }
+
+    pub fn set_auto_point_temp_hyst(&self, point: u64, millidegrees: i32) -> Result<()> {
+        self.hwmon.write_file(
+            format!("pwm{}_auto_point{}_temp_hyst", self.id, point),
+            millidegrees.to_string(),
+        )
+    }
+
+    /// Points of the automatic curve which the chip exposes, in order.
+    pub fn auto_points(&self) -> Result<Vec<HwMonPwmAutoPoint>> {
+        let mut points = Vec::<u64>::new();
+        for item in self.sensor.items() {
+            let point = item
+                .strip_prefix("auto_point")
+                .and_then(|item| item.split_once('_'))
+                .and_then(|(point, _)| point.parse().ok());
+            if let Some(point) = point {
+                if !points.contains(&point) {
+                    points.push(point);
+                }
+            }
+        }
+
+        points.sort_unstable();
+        Ok(points
+            .into_iter()
+            .map(|point| HwMonPwmAutoPoint {
+                point,
+                pwm: self.auto_point_pwm(point).ok(),
+                temp: self.auto_point_temp(point).ok(),
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use std::fs;
+
+    #[test]
+    fn setters() {
+        let dir = TempDir::new("hwmon-pwm");
+        for file in &[
+            "pwm1",
+            "pwm1_enable",
+            "pwm1_mode",
+            "pwm1_auto_channels_temp",
+            "pwm1_auto_point1_pwm",
+            "pwm1_auto_point1_temp",
+            "pwm1_auto_point1_temp_hyst",
+        ] {
+            fs::write(dir.path().join(file), "").unwrap();
+        }
+        fs::write(dir.path().join("pwm1"), "128\n").unwrap();
+
+        let hwmon = unsafe { HwMon::from_path_unchecked(dir.path().to_path_buf()) };
+        let pwm = hwmon.pwm(1).unwrap();
+        let read = |file: &str| fs::read_to_string(dir.path().join(file)).unwrap();
+
+        pwm.set_input(200).unwrap();
+        assert_eq!(pwm.input().unwrap(), 200);
+
+        for &enable in &[
+            HwMonPwmEnable::Off,
+            HwMonPwmEnable::Manual,
+            HwMonPwmEnable::Automatic,
+            HwMonPwmEnable::DriverSpecific(5),
+        ] {
+            fs::write(dir.path().join("pwm1_enable"), "").unwrap();
+            pwm.set_enable(enable).unwrap();
+            assert_eq!(pwm.enable().unwrap(), enable);
+        }
+        assert_eq!(read("pwm1_enable"), "5");
+
+        for &mode in &[HwMonPwmMode::Dc, HwMonPwmMode::Pwm] {
+            pwm.set_mode(mode).unwrap();
+            assert_eq!(pwm.mode().unwrap(), mode);
+        }
+        assert_eq!(read("pwm1_mode"), "1");
+
+        pwm.set_auto_channels_temp(0b101).unwrap();
+        pwm.set_auto_point_pwm(1, 96).unwrap();
+        pwm.set_auto_point_temp(1, -5000).unwrap();
+        pwm.set_auto_point_temp_hyst(1, 3000).unwrap();
+        assert_eq!(pwm.auto_channels_temp().unwrap(), 5);
+        assert_eq!(read("pwm1_auto_point1_temp_hyst"), "3000");
+
+        // Points are those discovered when the sensor was created.
+        let pwm = hwmon.pwm(1).unwrap();
+        assert_eq!(
+            pwm.auto_points().unwrap(),
+            [HwMonPwmAutoPoint {
+                point: 1,
+                pwm: Some(96),
+                temp: Some(-5000),
+            }]
+        );
     }
 }
@@ -0,0 +1,266 @@
+use crate::{HwMon, HwMonAlarms, SysClass};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::str::FromStr;
+
+/// A sensor of a hwmon device, such as `temp1`, with the items it supports.
+///
+/// Items are the suffixes of the attributes of the sensor, such as `input` for
+/// `temp1_input`. An attribute without a suffix, such as `pwm1`, is the empty item.
+#[derive(Clone, Debug)]
+pub struct HwMonSensor<'a> {
+    hwmon: &'a HwMon,
+    kind: String,
+    id: u64,
+    items: Vec<String>,
+}
+
+impl<'a> HwMonSensor<'a> {
+    /// Rebuilds a sensor from its parts, which were discovered earlier.
+    pub(crate) fn from_parts(hwmon: &'a HwMon, kind: &str, id: u64, items: Vec<String>) -> Self {
+        Self {
+            hwmon,
+            kind: kind.to_owned(),
+            id,
+            items,
+        }
+    }
+
+    pub fn hwmon(&self) -> &'a HwMon {
+        self.hwmon
+    }
+
+    /// The type of the sensor, such as `temp`, `fan` or `in`.
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn items(&self) -> &[String] {
+        &self.items
+    }
+
+    pub fn has(&self, item: &str) -> bool {
+        self.items.iter().any(|i| i == item)
+    }
+
+    /// The name of the attribute for `item`, such as `temp1_input`.
+    pub fn attribute(&self, item: &str) -> String {
+        attribute(&self.kind, self.id, item)
+    }
+
+    /// The label from the sensors config of the device, or else from the chip.
+    pub fn label(&self) -> Result<String> {
+        if let Some(label) = self.hwmon.configured_label(&self.kind, self.id) {
+            return Ok(label);
+        }
+
+        self.read("label")
+    }
+
+    pub fn read(&self, item: &str) -> Result<String> {
+        self.hwmon.trim_file(self.attribute(item))
+    }
+
+    pub fn parse<F: FromStr>(&self, item: &str) -> Result<F>
+    where
+        F::Err: Display,
+    {
+        self.hwmon.parse_file(self.attribute(item))
+    }
+}
+
+impl<'a> HwMonAlarms for HwMonSensor<'a> {
+    fn item_attribute(&self, item: &str) -> (&HwMon, String) {
+        (self.hwmon, self.attribute(item))
+    }
+}
+
+pub(crate) fn attribute(kind: &str, id: u64, item: &str) -> String {
+    if item.is_empty() {
+        format!("{}{}", kind, id)
+    } else {
+        format!("{}{}_{}", kind, id, item)
+    }
+}
+
+/// Splits an attribute name such as `temp1_input` into its type, number and item.
+fn parse_attribute(name: &str) -> Option<(&str, u64, &str)> {
+    let (sensor, item) = name.split_once('_').unwrap_or((name, ""));
+    let digits = sensor.find(|c: char| c.is_ascii_digit())?;
+    let (kind, id) = sensor.split_at(digits);
+    if kind.is_empty() || !kind.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    Some((kind, id.parse().ok()?, item))
+}
+
+impl HwMon {
+    /// Discovers every sensor of the device from the names of its attributes. Sensors
+    /// which the sensors config of the device ignores are skipped.
+    pub fn sensors(&self) -> Result<Vec<HwMonSensor<'_>>> {
+        let mut sensors = self.discover()?;
+        sensors.retain(|sensor| !self.is_ignored(&sensor.kind, sensor.id));
+        Ok(sensors)
+    }
+
+    /// Discovers every sensor of the device, including ignored ones.
+    fn discover(&self) -> Result<Vec<HwMonSensor<'_>>> {
+        let mut sensors = BTreeMap::<(String, u64), Vec<String>>::new();
+        for entry in fs::read_dir(self.path())? {
+            let name = entry?.file_name();
+            let name = match name.to_str() {
+                Some(name) => name,
+                None => continue,
+            };
+
+            if let Some((kind, id, item)) = parse_attribute(name) {
+                sensors
+                    .entry((kind.to_owned(), id))
+                    .or_default()
+                    .push(item.to_owned());
+            }
+        }
+
+        Ok(sensors
+            .into_iter()
+            .map(|((kind, id), mut items)| {
+                items.sort_unstable();
+                HwMonSensor {
+                    hwmon: self,
+                    kind,
+                    id,
+                    items,
+                }
+            })
+            .collect())
+    }
+
+    /// Finds a sensor by type and number, such as `temp` and `1`. Unlike `sensors`, this
+    /// finds sensors which the sensors config of the device ignores.
+    pub fn sensor(&self, kind: &str, id: u64) -> Result<HwMonSensor<'_>> {
+        self.discover()?
+            .into_iter()
+            .find(|sensor| sensor.kind == kind && sensor.id == id)
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::NotFound,
+                    format!("{}: sensor {}{} not found", self.id(), kind, id),
+                )
+            })
+    }
+
+    /// Discovers the sensors of one type, such as `temp`.
+    pub fn sensors_of(&self, kind: &str) -> Result<Vec<HwMonSensor<'_>>> {
+        let mut sensors = self.sensors()?;
+        sensors.retain(|sensor| sensor.kind == kind);
+        Ok(sensors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use crate::SensorsConfig;
+
+    #[test]
+    fn attribute_names() {
+        assert_eq!(
+            parse_attribute("temp1_crit_hyst"),
+            Some(("temp", 1, "crit_hyst"))
+        );
+        assert_eq!(parse_attribute("in0_input"), Some(("in", 0, "input")));
+        assert_eq!(
+            parse_attribute("power1_average_interval"),
+            Some(("power", 1, "average_interval"))
+        );
+        assert_eq!(parse_attribute("pwm2"), Some(("pwm", 2, "")));
+        assert_eq!(
+            parse_attribute("pwm2_auto_point1_pwm"),
+            Some(("pwm", 2, "auto_point1_pwm"))
+        );
+
+        for name in &[
+            "name",
+            "uevent",
+            "update_interval",
+            "temp_input",
+            "1_input",
+            "in0a_input",
+            "temp-1_input",
+        ] {
+            assert_eq!(parse_attribute(name), None, "{}", name);
+        }
+
+        assert_eq!(attribute("temp", 1, "crit_hyst"), "temp1_crit_hyst");
+        assert_eq!(attribute("pwm", 2, ""), "pwm2");
+    }
+
+    #[test]
+    fn discovery() {
+        let dir = TempDir::new("hwmon-discovery");
+        for file in &[
+            "name",
+            "uevent",
+            "temp1_input",
+            "temp1_crit_hyst",
+            "temp1_label",
+            "temp2_input",
+            "in0_input",
+            "pwm1",
+            "pwm1_enable",
+            "fan1_input",
+        ] {
+            fs::write(dir.path().join(file), "0\n").unwrap();
+        }
+        fs::create_dir(dir.path().join("power")).unwrap();
+        let hwmon = unsafe { HwMon::from_path_unchecked(dir.path().to_path_buf()) };
+
+        let sensors = hwmon
+            .sensors()
+            .unwrap()
+            .iter()
+            .map(|sensor| (sensor.attribute(""), sensor.items().to_vec()))
+            .collect::<Vec<_>>();
+        let items = |items: &[&str]| {
+            items
+                .iter()
+                .map(|&item| item.to_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            sensors,
+            [
+                ("fan1".to_owned(), items(&["input"])),
+                ("in0".to_owned(), items(&["input"])),
+                ("pwm1".to_owned(), items(&["", "enable"])),
+                ("temp1".to_owned(), items(&["crit_hyst", "input", "label"])),
+                ("temp2".to_owned(), items(&["input"])),
+            ]
+        );
+
+        let temps = hwmon.temps().unwrap();
+        assert_eq!(temps.len(), 2);
+        assert!(temps[0].sensor().has("crit_hyst"));
+        assert!(!temps[1].sensor().has("crit_hyst"));
+        assert!(hwmon.pwm(1).unwrap().sensor().has("enable"));
+        assert_eq!(
+            hwmon.sensor("temp", 3).unwrap_err().kind(),
+            ErrorKind::NotFound
+        );
+
+        // Ignored sensors are not listed, but can still be found by number.
+        let config = SensorsConfig::parse("chip \"*\"\nignore temp2\n").unwrap();
+        let hwmon = hwmon.with_sensors_config(&config).unwrap();
+        assert_eq!(hwmon.sensors_of("temp").unwrap().len(), 1);
+        assert_eq!(hwmon.sensor("temp", 2).unwrap().items(), ["input"]);
+        assert_eq!(hwmon.temp(2).unwrap().id(), 2);
+    }
+}
@@ -0,0 +1,827 @@
+use super::sensor::attribute;
+use crate::{HwMon, PciAddress, SysClass};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Configuration in the format of lm-sensors, as in /etc/sensors3.conf.
+///
+/// The `chip`, `label`, `compute`, `ignore` and `set` statements are supported. `bus`
+/// statements are accepted and ignored, so chips should be matched by a wildcard bus.
+#[derive(Clone, Debug, Default)]
+pub struct SensorsConfig {
+    chips: Vec<ChipConfig>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct ChipConfig {
+    patterns: Vec<String>,
+    statements: Vec<Statement>,
+}
+
+#[derive(Clone, Debug)]
+enum Statement {
+    Label(String, String),
+    Compute(String, Expr, Expr),
+    Ignore(String),
+    Set(String, Expr),
+}
+
+/// The statements of a `SensorsConfig` which apply to one chip.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SensorsChip {
+    labels: HashMap<String, String>,
+    computes: HashMap<String, (Expr, Expr)>,
+    ignores: HashSet<String>,
+    sets: Vec<(String, Expr)>,
+}
+
+impl SensorsConfig {
+    /// Loads /etc/sensors3.conf, or /etc/sensors.conf, and the files in /etc/sensors.d.
+    /// Missing files are skipped.
+    pub fn system() -> Result<Self> {
+        let mut config = SensorsConfig::default();
+        for path in &["/etc/sensors3.conf", "/etc/sensors.conf"] {
+            match fs::read_to_string(path) {
+                Ok(data) => {
+                    config.chips.extend(Self::parse(&data)?.chips);
+                    break;
+                }
+                Err(why) if why.kind() == ErrorKind::NotFound => continue,
+                Err(why) => return Err(why),
+            }
+        }
+
+        let entries = match fs::read_dir("/etc/sensors.d") {
+            Ok(entries) => entries,
+            Err(why) if why.kind() == ErrorKind::NotFound => return Ok(config),
+            Err(why) => return Err(why),
+        };
+
+        let mut paths = entries
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>>>()?;
+        paths.sort();
+        for path in paths {
+            config.chips.extend(Self::from_path(&path)?.chips);
+        }
+
+        Ok(config)
+    }
+
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(data: &str) -> Result<Self> {
+        let mut config = SensorsConfig::default();
+        for (number, line) in data.lines().enumerate() {
+            let invalid = |what: &str| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("sensors config line {}: {}", number + 1, what),
+                )
+            };
+
+            let line = strip_comment(line).trim();
+            let (keyword, rest) = match line.split_once(char::is_whitespace) {
+                Some((keyword, rest)) => (keyword, rest.trim()),
+                None if line.is_empty() => continue,
+                None => return Err(invalid("statement has no arguments")),
+            };
+
+            if keyword == "chip" {
+                config.chips.push(ChipConfig {
+                    patterns: words(rest).map_err(|why| invalid(&why))?,
+                    statements: Vec::new(),
+                });
+                continue;
+            } else if keyword == "bus" {
+                continue;
+            }
+
+            let chip = config
+                .chips
+                .last_mut()
+                .ok_or_else(|| invalid("statement before the first chip statement"))?;
+
+            let (feature, rest) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            let feature = feature.to_owned();
+            let statement = match keyword {
+                "label" => {
+                    let label = words(rest).map_err(|why| invalid(&why))?;
+                    Statement::Label(feature, label.join(" "))
+                }
+                "compute" => {
+                    let (from, to) = rest
+                        .split_once(',')
+                        .ok_or_else(|| invalid("compute needs two expressions"))?;
+                    Statement::Compute(
+                        feature,
+                        Expr::parse(from).map_err(|why| invalid(&why))?,
+                        Expr::parse(to).map_err(|why| invalid(&why))?,
+                    )
+                }
+                "ignore" => Statement::Ignore(feature),
+                "set" => Statement::Set(feature, Expr::parse(rest).map_err(|why| invalid(&why))?),
+                _ => return Err(invalid(&format!("unknown statement {}", keyword))),
+            };
+
+            chip.statements.push(statement);
+        }
+
+        Ok(config)
+    }
+
+    /// Collects the statements of every chip statement which matches `chip_name`. Later
+    /// statements take precedence over earlier ones.
+    pub(crate) fn chip(&self, chip_name: &str) -> SensorsChip {
+        let mut chip = SensorsChip::default();
+        let matching = self.chips.iter().filter(|config| {
+            config
+                .patterns
+                .iter()
+                .any(|pattern| chip_matches(pattern, chip_name))
+        });
+
+        for config in matching {
+            for statement in &config.statements {
+                match statement.clone() {
+                    Statement::Label(feature, label) => {
+                        chip.labels.insert(feature, label);
+                    }
+                    Statement::Compute(feature, from, to) => {
+                        chip.computes.insert(feature, (from, to));
+                    }
+                    Statement::Ignore(feature) => {
+                        chip.ignores.insert(feature);
+                    }
+                    Statement::Set(feature, value) => chip.sets.push((feature, value)),
+                }
+            }
+        }
+
+        chip
+    }
+}
+
+/// Removes a comment, which starts at a `#` outside of quotes, from a line.
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '#' if !quoted => return &line[..i],
+            _ => (),
+        }
+    }
+
+    line
+}
+
+/// Splits arguments into words, which may be quoted to contain spaces.
+fn words(s: &str) -> std::result::Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut rest = s.trim_start();
+    while !rest.is_empty() {
+        if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted
+                .find('"')
+                .ok_or_else(|| "unterminated string".to_owned())?;
+            words.push(quoted[..end].to_owned());
+            rest = quoted[end + 1..].trim_start();
+        } else {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            words.push(rest[..end].to_owned());
+            rest = rest[end..].trim_start();
+        }
+    }
+
+    Ok(words)
+}
+
+/// Matches a chip name such as `nct6775-isa-0290` against a pattern such as `nct6775-*`.
+/// A wildcard matches one part of the name, or every remaining part if it is last, of
+/// which there must be at least one.
+fn chip_matches(pattern: &str, chip_name: &str) -> bool {
+    let pattern = pattern.split('-').collect::<Vec<_>>();
+    let name = chip_name.split('-').collect::<Vec<_>>();
+    for (i, part) in pattern.iter().enumerate() {
+        if *part == "*" && i + 1 == pattern.len() {
+            return name.len() > i;
+        }
+
+        match name.get(i) {
+            Some(name) if *part == "*" || part.eq_ignore_ascii_case(name) => (),
+            _ => return false,
+        }
+    }
+
+    pattern.len() == name.len()
+}
+
+/// The factor between the sysfs value of a sensor type and its natural unit.
+fn scale(kind: &str) -> f64 {
+    match kind {
+        "in" | "curr" | "temp" | "humidity" => 1000.0,
+        "power" | "energy" => 1_000_000.0,
+        _ => 1.0,
+    }
+}
+
+/// Whether an item carries a value in the units of its sensor, to which `compute`
+/// statements and scaling apply, rather than a flag or setting such as `type` or `div`.
+fn is_limit(item: &str) -> bool {
+    let item = item.strip_suffix("_hyst").unwrap_or(item);
+    matches!(
+        item,
+        "min"
+            | "max"
+            | "lcrit"
+            | "crit"
+            | "emergency"
+            | "target"
+            | "offset"
+            | "cap"
+            | "average_min"
+            | "average_max"
+            | "rated_min"
+            | "rated_max"
+    )
+}
+
+/// Splits a feature such as `temp1` or `temp1_max` into its type, number and item.
+fn split_feature(feature: &str) -> Option<(&str, u64, &str)> {
+    let (sensor, item) = feature.split_once('_').unwrap_or((feature, ""));
+    let digits = sensor.find(|c: char| c.is_ascii_digit())?;
+    let (kind, id) = sensor.split_at(digits);
+    Some((kind, id.parse().ok()?, item))
+}
+
+impl HwMon {
+    /// The name of the chip in the format of lm-sensors, such as `nct6775-isa-0290` or
+    /// `k10temp-pci-00c3`, which is matched against `chip` statements.
+    pub fn chip_name(&self) -> Result<String> {
+        let name = self.name()?;
+        let device = match fs::canonicalize(self.path().join("device")) {
+            Ok(device) => device,
+            Err(why) if why.kind() == ErrorKind::NotFound => {
+                return Ok(format!("{}-virtual-0", name))
+            }
+            Err(why) => return Err(why),
+        };
+
+        let subsystem = fs::canonicalize(device.join("subsystem"))
+            .ok()
+            .and_then(|path| Some(path.file_name()?.to_str()?.to_owned()))
+            .unwrap_or_default();
+        let id = device
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default();
+
+        Ok(match subsystem.as_str() {
+            "pci" => {
+                let address = id.parse::<PciAddress>()?;
+                let address = (address.domain << 16)
+                    + (u32::from(address.bus) << 8)
+                    + (u32::from(address.device) << 3)
+                    + u32::from(address.function);
+                format!("{}-pci-{:04x}", name, address)
+            }
+            "i2c" => {
+                let (bus, address) = id.split_once('-').unwrap_or((id, "0"));
+                let address = u16::from_str_radix(address, 16).unwrap_or_default();
+                format!("{}-i2c-{}-{:02x}", name, bus, address)
+            }
+            "platform" | "isa" => {
+                let address = id
+                    .split_once('.')
+                    .and_then(|(_, address)| address.parse::<u32>().ok())
+                    .unwrap_or_default();
+                format!("{}-isa-{:04x}", name, address)
+            }
+            "" => format!("{}-virtual-0", name),
+            other => format!("{}-{}-0", name, other),
+        })
+    }
+
+    /// Applies the statements of `config` which match the chip to the labels, computed
+    /// values and sensor lists of the returned device.
+    pub fn with_sensors_config(mut self, config: &SensorsConfig) -> Result<Self> {
+        let chip = config.chip(&self.chip_name()?);
+        self.config = Some(Arc::new(chip));
+        Ok(self)
+    }
+
+    /// Writes the limits of the `set` statements which apply to the chip, as `sensors -s`
+    /// does.
+    ///
+    /// Limits such as `temp1_max` are converted with the inverse `compute` of their sensor
+    /// and scaled to the units of sysfs. Other attributes, such as `temp1_type`, `fan1_div`
+    /// or the chip-wide `beep_enable`, are written as given. Every statement is tried, and
+    /// the first failure is returned.
+    pub fn apply_sensors_sets(&self) -> Result<()> {
+        let chip = match self.config {
+            Some(ref chip) => chip,
+            None => return Ok(()),
+        };
+
+        let mut result = Ok(());
+        for (feature, value) in &chip.sets {
+            let applied = value
+                .eval(0.0, &|feature| self.feature_value(feature))
+                .and_then(|value| self.apply_sensors_set(feature, value));
+            if result.is_ok() {
+                result = applied;
+            }
+        }
+
+        result
+    }
+
+    fn apply_sensors_set(&self, feature: &str, value: f64) -> Result<()> {
+        let (attribute, value) = match split_feature(feature) {
+            Some((kind, id, item)) if is_limit(item) => (
+                attribute(kind, id, item),
+                self.compute_inverse(kind, id, value)? * scale(kind),
+            ),
+            _ => (feature.to_owned(), value),
+        };
+
+        // A value such as NaN would otherwise be written as 0.
+        if !value.is_finite() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("{}: set {} evaluates to {}", self.id(), feature, value),
+            ));
+        }
+
+        self.write_file(attribute, (value.round() as i64).to_string())
+    }
+
+    /// The label of a sensor from the configuration, if any.
+    pub(crate) fn configured_label(&self, kind: &str, id: u64) -> Option<String> {
+        let chip = self.config.as_ref()?;
+        chip.labels.get(&attribute(kind, id, "")).cloned()
+    }
+
+    pub(crate) fn is_ignored(&self, kind: &str, id: u64) -> bool {
+        self.config
+            .as_ref()
+            .is_some_and(|chip| chip.ignores.contains(&attribute(kind, id, "")))
+    }
+
+    /// Converts a value in natural units with the first expression of a `compute` statement.
+    pub(crate) fn compute(&self, kind: &str, id: u64, value: f64) -> Result<f64> {
+        match self.computes(kind, id) {
+            Some((from, _)) => from.eval(value, &|feature| self.feature_value(feature)),
+            None => Ok(value),
+        }
+    }
+
+    /// Converts a value back with the second expression of a `compute` statement.
+    pub(crate) fn compute_inverse(&self, kind: &str, id: u64, value: f64) -> Result<f64> {
+        match self.computes(kind, id) {
+            Some((_, to)) => to.eval(value, &|feature| self.feature_value(feature)),
+            None => Ok(value),
+        }
+    }
+
+    fn computes(&self, kind: &str, id: u64) -> Option<&(Expr, Expr)> {
+        let chip = self.config.as_ref()?;
+        chip.computes.get(&attribute(kind, id, ""))
+    }
+
+    /// The input of another feature, in natural units, for use in expressions.
+    fn feature_value(&self, feature: &str) -> Result<f64> {
+        let (kind, id, _) = split_feature(feature).ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("invalid feature {}", feature),
+            )
+        })?;
+
+        let raw = self.parse_file::<f64, _>(attribute(kind, id, "input"))?;
+        Ok(raw / scale(kind))
+    }
+}
+
+/// An expression of a `compute` or `set` statement.
+#[derive(Clone, Debug)]
+enum Expr {
+    Value(f64),
+    /// The value being converted, written as `@`.
+    Raw,
+    /// The input of another feature, such as `in3`.
+    Feature(String),
+    Neg(Box<Expr>),
+    /// `^x`, which is e raised to x.
+    Exp(Box<Expr>),
+    /// `` `x ``, which is the natural logarithm of x.
+    Ln(Box<Expr>),
+    Add(Box<Expr>, Box<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+    Div(Box<Expr>, Box<Expr>),
+}
+
+type Feature<'a> = &'a dyn Fn(&str) -> Result<f64>;
+
+impl Expr {
+    fn parse(s: &str) -> std::result::Result<Self, String> {
+        let mut parser = ExprParser {
+            chars: s.chars().filter(|c| !c.is_whitespace()).collect(),
+            pos: 0,
+        };
+
+        let expr = parser.sum()?;
+        match parser.peek() {
+            None => Ok(expr),
+            Some(c) => Err(format!("unexpected {:?} in expression {}", c, s.trim())),
+        }
+    }
+
+    fn eval(&self, raw: f64, feature: Feature) -> Result<f64> {
+        Ok(match self {
+            Expr::Value(value) => *value,
+            Expr::Raw => raw,
+            Expr::Feature(name) => feature(name)?,
+            Expr::Neg(a) => -a.eval(raw, feature)?,
+            Expr::Exp(a) => a.eval(raw, feature)?.exp(),
+            Expr::Ln(a) => a.eval(raw, feature)?.ln(),
+            Expr::Add(a, b) => a.eval(raw, feature)? + b.eval(raw, feature)?,
+            Expr::Sub(a, b) => a.eval(raw, feature)? - b.eval(raw, feature)?,
+            Expr::Mul(a, b) => a.eval(raw, feature)? * b.eval(raw, feature)?,
+            Expr::Div(a, b) => a.eval(raw, feature)? / b.eval(raw, feature)?,
+        })
+    }
+}
+
+struct ExprParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl ExprParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn sum(&mut self) -> std::result::Result<Expr, String> {
+        let mut expr = self.product()?;
+        loop {
+            match self.peek() {
+                Some('+') => {
+                    self.pos += 1;
+                    expr = Expr::Add(Box::new(expr), Box::new(self.product()?));
+                }
+                Some('-') => {
+                    self.pos += 1;
+                    expr = Expr::Sub(Box::new(expr), Box::new(self.product()?));
+                }
+                _ => return Ok(expr),
+            }
+        }
+    }
+
+    fn product(&mut self) -> std::result::Result<Expr, String> {
+        let mut expr = self.unary()?;
+        loop {
+            match self.peek() {
+                Some('*') => {
+                    self.pos += 1;
+                    expr = Expr::Mul(Box::new(expr), Box::new(self.unary()?));
+                }
+                Some('/') => {
+                    self.pos += 1;
+                    expr = Expr::Div(Box::new(expr), Box::new(self.unary()?));
+                }
+                _ => return Ok(expr),
+            }
+        }
+    }
+
+    fn unary(&mut self) -> std::result::Result<Expr, String> {
+        let wrap: fn(Box<Expr>) -> Expr = match self.peek() {
+            Some('-') => Expr::Neg,
+            Some('^') => Expr::Exp,
+            Some('`') => Expr::Ln,
+            _ => return self.primary(),
+        };
+
+        self.pos += 1;
+        Ok(wrap(Box::new(self.unary()?)))
+    }
+
+    fn primary(&mut self) -> std::result::Result<Expr, String> {
+        match self.peek() {
+            Some('@') => {
+                self.pos += 1;
+                Ok(Expr::Raw)
+            }
+            Some('(') => {
+                self.pos += 1;
+                let expr = self.sum()?;
+                if self.peek() != Some(')') {
+                    return Err("missing )".to_owned());
+                }
+                self.pos += 1;
+                Ok(expr)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let start = self.pos;
+                while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '.') {
+                    self.pos += 1;
+                }
+
+                let number = self.chars[start..self.pos].iter().collect::<String>();
+                number
+                    .parse()
+                    .map(Expr::Value)
+                    .map_err(|_| format!("invalid number {}", number))
+            }
+            Some(c) if c.is_ascii_alphabetic() => {
+                let start = self.pos;
+                while self
+                    .peek()
+                    .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+                {
+                    self.pos += 1;
+                }
+
+                Ok(Expr::Feature(self.chars[start..self.pos].iter().collect()))
+            }
+            Some(c) => Err(format!("unexpected {:?}", c)),
+            None => Err("expression ends early".to_owned()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use std::os::unix::fs::symlink;
+
+    fn eval(expr: &str, raw: f64) -> f64 {
+        let features = |feature: &str| match feature {
+            "in3" => Ok(3.3),
+            _ => Err(Error::from(ErrorKind::NotFound)),
+        };
+        Expr::parse(expr).unwrap().eval(raw, &features).unwrap()
+    }
+
+    #[test]
+    fn expressions() {
+        assert_eq!(eval("@", 1.5), 1.5);
+        assert_eq!(eval("@ * 2", 1.5), 3.0);
+        assert_eq!(eval("@*2 + 1", 1.5), 4.0);
+        assert_eq!(eval("1 + @*2", 1.5), 4.0);
+        assert_eq!(eval("(1 + @) * 2", 1.5), 5.0);
+        assert_eq!(eval("10 - 4 - 3", 0.0), 3.0);
+        assert_eq!(eval("12 / 3 / 2", 0.0), 2.0);
+        assert_eq!(eval("-@ + 1", 2.0), -1.0);
+        assert_eq!(eval("--@", 2.0), 2.0);
+        assert!((eval("@ * (6.8/10) + 0.5", 10.0) - 7.3).abs() < 1e-9);
+        assert!((eval("in3 * 2", 0.0) - 6.6).abs() < 1e-9);
+        assert!((eval("^0", 0.0) - 1.0).abs() < 1e-9);
+        assert!((eval("`^2", 0.0) - 2.0).abs() < 1e-9);
+        assert!(eval("(@-@)/0", 1.0).is_nan());
+
+        for invalid in &["", "@ +", "(@", "@)", "1.2.3", "@ % 2", "2 @"] {
+            assert!(Expr::parse(invalid).is_err(), "{:?}", invalid);
+        }
+
+        let err = Expr::parse("in4 * 2")
+            .unwrap()
+            .eval(0.0, &|_| Err(Error::from(ErrorKind::NotFound)))
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn chip_patterns() {
+        assert!(chip_matches("nct6775-*", "nct6775-isa-0290"));
+        assert!(chip_matches("NCT6775-*", "nct6775-isa-0290"));
+        assert!(chip_matches("nct6775-isa-*", "nct6775-isa-0290"));
+        assert!(chip_matches("nct6775-isa-0290", "nct6775-isa-0290"));
+        assert!(chip_matches("*-isa-0290", "nct6775-isa-0290"));
+        assert!(chip_matches("*", "nct6775-isa-0290"));
+        assert!(chip_matches("lm75-i2c-*-4c", "lm75-i2c-3-4c"));
+        assert!(chip_matches("lm75-*-3-4c", "lm75-i2c-3-4c"));
+
+        assert!(!chip_matches("nct6775", "nct6775-isa-0290"));
+        assert!(!chip_matches("nct6776-*", "nct6775-isa-0290"));
+        assert!(!chip_matches("nct6775-pci-*", "nct6775-isa-0290"));
+        assert!(!chip_matches("nct6775-isa-0290-*", "nct6775-isa-0290"));
+        assert!(!chip_matches("lm75-i2c-*-4d", "lm75-i2c-3-4c"));
+    }
+
+    #[test]
+    fn parse_config() {
+        let config = SensorsConfig::parse(
+            r#"
+            # Comment
+            bus "i2c-3" "SMBus adapter"
+
+            chip "nct6775-*" "nct6776-*"
+                label temp1 "CPU Temp"   # trailing comment
+                label temp2 "Core #0"  # comment with "quotes"
+                label in0 Vcore
+                compute in0 @*2, @/2
+                ignore fan2
+                set temp1_max 70
+
+            chip "nct6775-isa-0290"
+                label temp1 "Package"
+            "#,
+        )
+        .unwrap();
+
+        let chip = config.chip("nct6775-isa-0290");
+        assert_eq!(chip.labels["temp1"], "Package");
+        assert_eq!(chip.labels["in0"], "Vcore");
+        assert!(chip.computes.contains_key("in0"));
+        assert!(chip.ignores.contains("fan2"));
+        assert_eq!(chip.sets.len(), 1);
+
+        let chip = config.chip("nct6776-isa-0a30");
+        assert_eq!(chip.labels["temp1"], "CPU Temp");
+        assert_eq!(chip.labels["temp2"], "Core #0");
+
+        let chip = config.chip("k10temp-pci-00c3");
+        assert!(chip.labels.is_empty() && chip.sets.is_empty());
+
+        for invalid in &[
+            "label temp1 CPU",
+            "chip \"a-*\"\nlabel temp1 \"CPU",
+            "chip \"a-*\"\ncompute in0 @*2",
+            "chip \"a-*\"\ncompute in0 @*, @",
+            "chip \"a-*\"\nfrobnicate in0",
+            "chip",
+        ] {
+            let err = SensorsConfig::parse(invalid).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidData, "{:?}", invalid);
+        }
+    }
+
+    /// Creates a hwmon device named `name` under `dir`, with a parent device `device` on
+    /// `subsystem`, if given.
+    fn hwmon(dir: &TempDir, name: &str, parent: Option<(&str, &str)>) -> HwMon {
+        let path = dir.path().join(name);
+        fs::create_dir_all(&path).unwrap();
+        fs::write(path.join("name"), name).unwrap();
+
+        if let Some((subsystem, device)) = parent {
+            let subsystem_dir = dir.path().join("bus").join(subsystem);
+            let device_dir = dir.path().join("devices").join(device);
+            fs::create_dir_all(&subsystem_dir).unwrap();
+            fs::create_dir_all(&device_dir).unwrap();
+            symlink(&subsystem_dir, device_dir.join("subsystem")).unwrap();
+            symlink(&device_dir, path.join("device")).unwrap();
+        }
+
+        unsafe { HwMon::from_path_unchecked(path) }
+    }
+
+    #[test]
+    fn chip_names() {
+        let dir = TempDir::new("chip-name");
+        let cases = [
+            ("k10temp", Some(("pci", "0000:00:18.3")), "k10temp-pci-00c3"),
+            ("amdgpu", Some(("pci", "0001:03:00.0")), "amdgpu-pci-10300"),
+            ("lm75", Some(("i2c", "3-004c")), "lm75-i2c-3-4c"),
+            (
+                "nct6775",
+                Some(("platform", "nct6775.656")),
+                "nct6775-isa-0290",
+            ),
+            ("acpitz", None, "acpitz-virtual-0"),
+            ("nvme", Some(("nvme", "nvme0")), "nvme-nvme-0"),
+        ];
+
+        for (name, parent, chip_name) in &cases {
+            let hwmon = hwmon(&dir, name, *parent);
+            assert_eq!(hwmon.chip_name().unwrap(), *chip_name);
+        }
+    }
+
+    #[test]
+    fn sets() {
+        let dir = TempDir::new("sensors-sets");
+        let hwmon = hwmon(&dir, "nct6775", None);
+        for file in &[
+            "temp1_max",
+            "temp1_max_hyst",
+            "temp1_type",
+            "in0_min",
+            "fan1_div",
+            "fan1_min",
+            "beep_enable",
+        ] {
+            fs::write(hwmon.path().join(file), "").unwrap();
+        }
+        fs::write(hwmon.path().join("in3_input"), "3300").unwrap();
+
+        let config = SensorsConfig::parse(
+            r#"
+            chip "nct6775-*"
+                compute temp1 @+1, @-1
+                compute in0 @*2, @/2
+                compute fan1 @*2, @/2
+                set temp1_max 71
+                set temp1_max_hyst 66
+                set temp1_type 4
+                set in0_min in3 * 0.5
+                set fan1_div 4
+                set fan1_min 600
+                set beep_enable 0
+            "#,
+        )
+        .unwrap();
+        let hwmon = hwmon.with_sensors_config(&config).unwrap();
+        hwmon.apply_sensors_sets().unwrap();
+
+        let read = |file: &str| fs::read_to_string(hwmon.path().join(file)).unwrap();
+        assert_eq!(read("temp1_max"), "70000");
+        assert_eq!(read("temp1_max_hyst"), "65000");
+        assert_eq!(read("temp1_type"), "4");
+        assert_eq!(read("in0_min"), "825");
+        assert_eq!(read("fan1_div"), "4");
+        assert_eq!(read("fan1_min"), "300");
+        assert_eq!(read("beep_enable"), "0");
+    }
+
+    #[test]
+    fn sets_continue_after_failure() {
+        let dir = TempDir::new("sensors-sets-failure");
+        let hwmon = hwmon(&dir, "nct6775", None);
+        fs::write(hwmon.path().join("temp1_max"), "").unwrap();
+
+        let config =
+            SensorsConfig::parse("chip \"nct6775-*\"\nset temp2_max 80\nset temp1_max 70\n")
+                .unwrap();
+        let hwmon = hwmon.with_sensors_config(&config).unwrap();
+        assert!(hwmon.apply_sensors_sets().is_err());
+        assert_eq!(
+            fs::read_to_string(hwmon.path().join("temp1_max")).unwrap(),
+            "70000"
+        );
+    }
+
+    #[test]
+    fn sets_reject_non_finite_values() {
+        let dir = TempDir::new("sensors-sets-non-finite");
+        let hwmon = hwmon(&dir, "nct6775", None);
+        for file in &["temp1_max", "fan1_div"] {
+            fs::write(hwmon.path().join(file), "").unwrap();
+        }
+
+        for (config, file) in &[
+            ("set temp1_max (1-1)/0", "temp1_max"),
+            ("compute temp1 @, @/0\nset temp1_max 70", "temp1_max"),
+            ("set fan1_div 1/0", "fan1_div"),
+        ] {
+            let config = format!("chip \"nct6775-*\"\n{}\n", config);
+            let config = SensorsConfig::parse(&config).unwrap();
+            let hwmon = hwmon.clone().with_sensors_config(&config).unwrap();
+            let err = hwmon.apply_sensors_sets().unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidData, "{}", file);
+            assert_eq!(fs::read_to_string(hwmon.path().join(file)).unwrap(), "");
+        }
+    }
+
+    #[test]
+    fn labels_computes_and_ignores() {
+        let dir = TempDir::new("sensors-apply");
+        let hwmon = hwmon(&dir, "nct6775", None);
+        for (file, value) in &[
+            ("temp1_input", "45000"),
+            ("temp1_label", "SYSTIN"),
+            ("in0_input", "600"),
+            ("fan1_input", "1200"),
+            ("fan2_input", "0"),
+        ] {
+            fs::write(hwmon.path().join(file), value).unwrap();
+        }
+
+        let config = SensorsConfig::parse(
+            "chip \"nct6775-*\"\nlabel temp1 \"CPU\"\ncompute in0 @*2, @/2\nignore fan2\n",
+        )
+        .unwrap();
+        let hwmon = hwmon.with_sensors_config(&config).unwrap();
+
+        assert_eq!(hwmon.temp(1).unwrap().label().unwrap(), "CPU");
+        assert_eq!(hwmon.voltage(0).unwrap().input_volts().unwrap().0, 1.2);
+        let sensors = hwmon
+            .sensors()
+            .unwrap()
+            .iter()
+            .map(|sensor| format!("{}{}", sensor.kind(), sensor.id()))
+            .collect::<Vec<_>>();
+        assert_eq!(sensors, vec!["fan1", "in0", "temp1"]);
+    }
+}
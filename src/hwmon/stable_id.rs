@@ -0,0 +1,229 @@
+use crate::{HwMon, SysClass};
+use std::fmt;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::str::FromStr;
+
+/// Identifies a hwmon device across reboots, unlike its `hwmonN` id.
+///
+/// The string form is `name[:driver][@device]`, such as
+/// `nct6775:nct6775@platform/nct6775.656` or `k10temp:k10temp@pci0000:00/0000:00:18.3`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct HwMonStableId {
+    pub name: String,
+    /// The driver bound to the parent device.
+    pub driver: Option<String>,
+    /// Path of the parent device below /sys/devices, which contains its bus address.
+    pub device: Option<String>,
+}
+
+impl HwMonStableId {
+    /// Whether `other` is the same device as this one, comparing only the fields which
+    /// this one has. An id of just `k10temp` matches any `k10temp` device.
+    pub fn matches(&self, other: &HwMonStableId) -> bool {
+        self.name == other.name
+            && self
+                .driver
+                .as_ref()
+                .is_none_or(|driver| other.driver.as_ref() == Some(driver))
+            && self
+                .device
+                .as_ref()
+                .is_none_or(|device| other.device.as_ref() == Some(device))
+    }
+}
+
+impl fmt::Display for HwMonStableId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.name)?;
+        if let Some(ref driver) = self.driver {
+            write!(f, ":{}", driver)?;
+        }
+        if let Some(ref device) = self.device {
+            write!(f, "@{}", device)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for HwMonStableId {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        let (left, device) = match s.split_once('@') {
+            Some((left, device)) => (left, Some(device)),
+            None => (s, None),
+        };
+
+        let (name, driver) = match left.split_once(':') {
+            Some((name, driver)) => (name, Some(driver)),
+            None => (left, None),
+        };
+
+        if name.is_empty() || driver == Some("") || device == Some("") {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid hwmon id: {}", s),
+            ));
+        }
+
+        Ok(HwMonStableId {
+            name: name.to_owned(),
+            driver: driver.map(str::to_owned),
+            device: device.map(str::to_owned),
+        })
+    }
+}
+
+impl HwMon {
+    /// The identifier of the device, which is the same across reboots.
+    pub fn stable_id(&self) -> Result<HwMonStableId> {
+        let name = self.name()?;
+
+        // Virtual devices, such as those of thermal zones, have no parent device.
+        let device = match fs::canonicalize(self.path().join("device")) {
+            Ok(device) => Some(device),
+            Err(why) if why.kind() == ErrorKind::NotFound => None,
+            Err(why) => return Err(why),
+        };
+
+        let driver = match device {
+            Some(ref device) => fs::canonicalize(device.join("driver"))
+                .ok()
+                .and_then(|driver| Some(driver.file_name()?.to_str()?.to_owned())),
+            None => None,
+        };
+
+        let device = device.map(|device| {
+            device
+                .strip_prefix("/sys/devices")
+                .unwrap_or(&device)
+                .to_string_lossy()
+                .trim_start_matches('/')
+                .to_owned()
+        });
+
+        Ok(HwMonStableId {
+            name,
+            driver,
+            device,
+        })
+    }
+
+    /// Finds the device with a stable identifier from `HwMon::stable_id`. Fields which
+    /// are missing from `id` match any value, but if more than one device matches, an
+    /// error is returned rather than picking one.
+    pub fn find_stable(id: &HwMonStableId) -> Result<HwMon> {
+        find_stable_in(HwMon::all()?, id)
+    }
+}
+
+fn find_stable_in(devices: Vec<HwMon>, id: &HwMonStableId) -> Result<HwMon> {
+    let mut matching = devices
+        .into_iter()
+        .filter(|hwmon| hwmon.stable_id().is_ok_and(|stable| id.matches(&stable)));
+
+    let hwmon = matching
+        .next()
+        .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("hwmon {} not found", id)))?;
+
+    if let Some(other) = matching.next() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "hwmon {} is ambiguous: {} and {} both match",
+                id,
+                hwmon.id(),
+                other.id()
+            ),
+        ));
+    }
+
+    Ok(hwmon)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use std::os::unix::fs::symlink;
+
+    fn id(s: &str) -> HwMonStableId {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_and_display() {
+        for s in &[
+            "k10temp",
+            "k10temp:k10temp",
+            "k10temp@pci0000:00/0000:00:18.3",
+            "nct6775:nct6775@platform/nct6775.656",
+        ] {
+            assert_eq!(id(s).to_string(), *s);
+        }
+
+        let full = id("nct6775:nct6775@platform/nct6775.656");
+        assert_eq!(full.name, "nct6775");
+        assert_eq!(full.driver.as_deref(), Some("nct6775"));
+        assert_eq!(full.device.as_deref(), Some("platform/nct6775.656"));
+
+        for s in &["", ":driver", "name:", "name@", "name:@device"] {
+            assert!(s.parse::<HwMonStableId>().is_err(), "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn partial_match() {
+        let device = id("k10temp:k10temp@pci0000:00/0000:00:18.3");
+        assert!(id("k10temp").matches(&device));
+        assert!(id("k10temp:k10temp").matches(&device));
+        assert!(id("k10temp@pci0000:00/0000:00:18.3").matches(&device));
+        assert!(device.matches(&device));
+
+        assert!(!id("nct6775").matches(&device));
+        assert!(!id("k10temp:nvme").matches(&device));
+        assert!(!id("k10temp@pci0000:00/0000:00:18.4").matches(&device));
+
+        // Fields which the query has must also be present on the device.
+        assert!(!device.matches(&id("k10temp")));
+    }
+
+    /// Creates `hwmonN` with a parent device `device` bound to `driver`.
+    fn hwmon(dir: &TempDir, n: u32, name: &str, device: &str, driver: &str) -> HwMon {
+        let driver_dir = dir.path().join("drivers").join(driver);
+        let device_dir = dir.path().join("devices").join(device);
+        fs::create_dir_all(&driver_dir).unwrap();
+        fs::create_dir_all(&device_dir).unwrap();
+        let _ = symlink(&driver_dir, device_dir.join("driver"));
+
+        let path = dir.path().join(format!("hwmon{}", n));
+        fs::create_dir_all(&path).unwrap();
+        fs::write(path.join("name"), name).unwrap();
+        symlink(&device_dir, path.join("device")).unwrap();
+        unsafe { HwMon::from_path_unchecked(path) }
+    }
+
+    #[test]
+    fn find_stable() {
+        let dir = TempDir::new("stable-id");
+        let devices = vec![
+            hwmon(&dir, 0, "k10temp", "0000:00:18.3", "k10temp"),
+            hwmon(&dir, 1, "nvme", "nvme0", "nvme"),
+            hwmon(&dir, 2, "nvme", "nvme1", "nvme"),
+        ];
+
+        let found = find_stable_in(devices.clone(), &id("k10temp")).unwrap();
+        assert_eq!(found.id(), "hwmon0");
+
+        let nvme1 = devices[2].stable_id().unwrap();
+        assert_eq!(nvme1.driver.as_deref(), Some("nvme"));
+        let found = find_stable_in(devices.clone(), &nvme1).unwrap();
+        assert_eq!(found.id(), "hwmon2");
+
+        let err = find_stable_in(devices.clone(), &id("nvme:nvme")).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+        let err = find_stable_in(devices, &id("nct6775")).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+    }
+}
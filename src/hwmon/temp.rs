@@ -1,45 +1,192 @@
-use crate::{HwMon, SysClass};
+use crate::{Celsius, HwMon, HwMonAlarms, HwMonSensor, SysClass};
 use std::io::Result;
 
 pub struct HwMonTemp<'a> {
     hwmon: &'a HwMon,
     id: u64,
+    sensor: HwMonSensor<'a>,
 }
 
 impl<'a> HwMonTemp<'a> {
     pub fn new(hwmon: &'a HwMon, id: u64) -> Result<Self> {
-        let s = Self { hwmon, id };
+        let s = Self::from_sensor(hwmon.sensor("temp", id)?);
 
         s.input()?;
 
         Ok(s)
     }
 
+    pub(crate) fn from_sensor(sensor: HwMonSensor<'a>) -> Self {
+        Self {
+            hwmon: sensor.hwmon(),
+            id: sensor.id(),
+            sensor,
+        }
+    }
+
+    pub fn hwmon(&self) -> &'a HwMon {
+        self.hwmon
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// The sensor with every item it supports, as discovered when this was created.
+    pub fn sensor(&self) -> &HwMonSensor<'a> {
+        &self.sensor
+    }
+
+    /// The label from the sensors config of the device, or else from the chip.
     pub fn label(&self) -> Result<String> {
-        self.hwmon.trim_file(&format!("temp{}_label", self.id))
+        if let Some(label) = self.hwmon.configured_label("temp", self.id) {
+            return Ok(label);
+        }
+
+        self.hwmon.trim_file(format!("temp{}_label", self.id))
     }
 
     pub fn input(&self) -> Result<i32> {
-        self.hwmon.parse_file(&format!("temp{}_input", self.id))
+        self.hwmon.parse_file(format!("temp{}_input", self.id))
     }
 
     pub fn lcrit(&self) -> Result<i32> {
-        self.hwmon.parse_file(&format!("temp{}_lcrit", self.id))
+        self.hwmon.parse_file(format!("temp{}_lcrit", self.id))
     }
 
     pub fn min(&self) -> Result<i32> {
-        self.hwmon.parse_file(&format!("temp{}_min", self.id))
+        self.hwmon.parse_file(format!("temp{}_min", self.id))
+    }
+
+    /// Sets the limit, in millidegrees Celsius.
+    pub fn set_min(&self, millidegrees: i32) -> Result<()> {
+        self.hwmon
+            .write_file(format!("temp{}_min", self.id), millidegrees.to_string())
     }
 
     pub fn max(&self) -> Result<i32> {
-        self.hwmon.parse_file(&format!("temp{}_max", self.id))
+        self.hwmon.parse_file(format!("temp{}_max", self.id))
+    }
+
+    /// Sets the limit, in millidegrees Celsius.
+    pub fn set_max(&self, millidegrees: i32) -> Result<()> {
+        self.hwmon
+            .write_file(format!("temp{}_max", self.id), millidegrees.to_string())
     }
 
     pub fn crit(&self) -> Result<i32> {
-        self.hwmon.parse_file(&format!("temp{}_crit", self.id))
+        self.hwmon.parse_file(format!("temp{}_crit", self.id))
+    }
+
+    /// Sets the limit, in millidegrees Celsius.
+    pub fn set_crit(&self, millidegrees: i32) -> Result<()> {
+        self.hwmon
+            .write_file(format!("temp{}_crit", self.id), millidegrees.to_string())
     }
 
     pub fn emergency(&self) -> Result<i32> {
-        self.hwmon.parse_file(&format!("temp{}_emergency", self.id))
+        self.hwmon.parse_file(format!("temp{}_emergency", self.id))
+    }
+
+    /// Applies the `compute` statement of the sensors config of the device, if any.
+    fn computed(&self, value: Celsius) -> Result<Celsius> {
+        self.hwmon.compute("temp", self.id, value.0).map(Celsius)
+    }
+
+    pub fn input_celsius(&self) -> Result<Celsius> {
+        self.input()
+            .map(Celsius::from_millidegrees)
+            .and_then(|value| self.computed(value))
+    }
+
+    pub fn lcrit_celsius(&self) -> Result<Celsius> {
+        self.lcrit()
+            .map(Celsius::from_millidegrees)
+            .and_then(|value| self.computed(value))
+    }
+
+    pub fn min_celsius(&self) -> Result<Celsius> {
+        self.min()
+            .map(Celsius::from_millidegrees)
+            .and_then(|value| self.computed(value))
+    }
+
+    pub fn set_min_celsius(&self, limit: Celsius) -> Result<()> {
+        let limit = self.hwmon.compute_inverse("temp", self.id, limit.0)?;
+        self.set_min(Celsius(limit).millidegrees())
+    }
+
+    pub fn max_celsius(&self) -> Result<Celsius> {
+        self.max()
+            .map(Celsius::from_millidegrees)
+            .and_then(|value| self.computed(value))
+    }
+
+    pub fn set_max_celsius(&self, limit: Celsius) -> Result<()> {
+        let limit = self.hwmon.compute_inverse("temp", self.id, limit.0)?;
+        self.set_max(Celsius(limit).millidegrees())
+    }
+
+    pub fn crit_celsius(&self) -> Result<Celsius> {
+        self.crit()
+            .map(Celsius::from_millidegrees)
+            .and_then(|value| self.computed(value))
+    }
+
+    pub fn set_crit_celsius(&self, limit: Celsius) -> Result<()> {
+        let limit = self.hwmon.compute_inverse("temp", self.id, limit.0)?;
+        self.set_crit(Celsius(limit).millidegrees())
+    }
+
+    pub fn emergency_celsius(&self) -> Result<Celsius> {
+        self.emergency()
+            .map(Celsius::from_millidegrees)
+            .and_then(|value| self.computed(value))
+    }
+}
+
+impl<'a> HwMonAlarms for HwMonTemp<'a> {
+    fn item_attribute(&self, item: &str) -> (&HwMon, String) {
+        (self.hwmon, format!("temp{}_{}", self.id, item))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use crate::SensorsConfig;
+    use std::fs;
+
+    #[test]
+    fn computed_limits() {
+        let dir = TempDir::new("hwmon-temp");
+        for (file, value) in &[
+            ("name", "nct6775"),
+            ("temp1_input", "40000"),
+            ("temp1_min", ""),
+            ("temp1_max", ""),
+            ("temp1_crit", ""),
+        ] {
+            fs::write(dir.path().join(file), value).unwrap();
+        }
+
+        let config =
+            SensorsConfig::parse("chip \"nct6775-*\"\ncompute temp1 @*2+5, (@-5)/2\n").unwrap();
+        let hwmon = unsafe { HwMon::from_path_unchecked(dir.path().to_path_buf()) }
+            .with_sensors_config(&config)
+            .unwrap();
+        let temp = hwmon.temp(1).unwrap();
+        assert_eq!(temp.input_celsius().unwrap(), Celsius(85.0));
+
+        // Limits in natural units are written back with the inverse expression.
+        temp.set_min_celsius(Celsius(5.0)).unwrap();
+        temp.set_max_celsius(Celsius(85.0)).unwrap();
+        temp.set_crit_celsius(Celsius(-15.5)).unwrap();
+        assert_eq!(temp.min().unwrap(), 0);
+        assert_eq!(temp.max().unwrap(), 40000);
+        assert_eq!(temp.crit().unwrap(), -10250);
+        assert_eq!(temp.max_celsius().unwrap(), Celsius(85.0));
+        assert_eq!(temp.crit_celsius().unwrap(), Celsius(-15.5));
     }
 }
@@ -0,0 +1,174 @@
+use std::fmt;
+
+/// Writes `value` with the precision of the formatter, or `default` decimals.
+fn write_scaled(f: &mut fmt::Formatter, value: f64, default: usize, unit: &str) -> fmt::Result {
+    let precision = f.precision().unwrap_or(default);
+    write!(f, "{:.*} {}", precision, value, unit)
+}
+
+/// A temperature in degrees Celsius. Hwmon reports temperatures in millidegrees.
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct Celsius(pub f64);
+
+impl Celsius {
+    pub fn from_millidegrees(millidegrees: i32) -> Self {
+        Celsius(f64::from(millidegrees) / 1000.0)
+    }
+
+    pub fn millidegrees(self) -> i32 {
+        (self.0 * 1000.0).round() as i32
+    }
+
+    pub fn fahrenheit(self) -> f64 {
+        self.0 * 9.0 / 5.0 + 32.0
+    }
+
+    pub fn kelvin(self) -> f64 {
+        self.0 + 273.15
+    }
+}
+
+impl fmt::Display for Celsius {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_scaled(f, self.0, 1, "°C")
+    }
+}
+
+/// A fan speed in revolutions per minute, which is how hwmon reports it.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub struct Rpm(pub u32);
+
+impl fmt::Display for Rpm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} RPM", self.0)
+    }
+}
+
+/// A voltage in volts. Hwmon reports voltages in millivolts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct Volts(pub f64);
+
+impl Volts {
+    pub fn from_millivolts(millivolts: i32) -> Self {
+        Volts(f64::from(millivolts) / 1000.0)
+    }
+
+    pub fn millivolts(self) -> i32 {
+        (self.0 * 1000.0).round() as i32
+    }
+}
+
+impl fmt::Display for Volts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_scaled(f, self.0, 3, "V")
+    }
+}
+
+/// A current in amperes. Hwmon reports currents in milliamperes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct Amps(pub f64);
+
+impl Amps {
+    pub fn from_milliamps(milliamps: i32) -> Self {
+        Amps(f64::from(milliamps) / 1000.0)
+    }
+
+    pub fn milliamps(self) -> i32 {
+        (self.0 * 1000.0).round() as i32
+    }
+}
+
+impl fmt::Display for Amps {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_scaled(f, self.0, 3, "A")
+    }
+}
+
+/// A power in watts. Hwmon reports power in microwatts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct Watts(pub f64);
+
+impl Watts {
+    pub fn from_microwatts(microwatts: u64) -> Self {
+        Watts(microwatts as f64 / 1_000_000.0)
+    }
+
+    pub fn microwatts(self) -> u64 {
+        (self.0 * 1_000_000.0).round() as u64
+    }
+}
+
+impl fmt::Display for Watts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_scaled(f, self.0, 2, "W")
+    }
+}
+
+/// An energy in joules. Hwmon reports energy in microjoules.
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct Joules(pub f64);
+
+impl Joules {
+    pub fn from_microjoules(microjoules: u64) -> Self {
+        Joules(microjoules as f64 / 1_000_000.0)
+    }
+
+    pub fn microjoules(self) -> u64 {
+        (self.0 * 1_000_000.0).round() as u64
+    }
+
+    pub fn watt_hours(self) -> f64 {
+        self.0 / 3600.0
+    }
+}
+
+impl fmt::Display for Joules {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_scaled(f, self.0, 3, "J")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn celsius() {
+        let temp = Celsius::from_millidegrees(45_500);
+        assert_eq!(temp, Celsius(45.5));
+        assert_eq!(temp.millidegrees(), 45_500);
+        assert_eq!(temp.fahrenheit(), 113.9);
+        assert_eq!(temp.kelvin(), 318.65);
+
+        let cold = Celsius::from_millidegrees(-40_000);
+        assert_eq!(cold.millidegrees(), -40_000);
+        assert_eq!(cold.fahrenheit(), -40.0);
+        assert_eq!(Celsius(-273.15).kelvin(), 0.0);
+    }
+
+    #[test]
+    fn electrical() {
+        assert_eq!(Volts::from_millivolts(1_215), Volts(1.215));
+        assert_eq!(Volts(-0.5).millivolts(), -500);
+        assert_eq!(Amps::from_milliamps(-1_250), Amps(-1.25));
+        assert_eq!(Amps(2.5).milliamps(), 2_500);
+        assert_eq!(Watts::from_microwatts(12_500_000), Watts(12.5));
+        assert_eq!(Watts(0.000_001).microwatts(), 1);
+        assert_eq!(Joules::from_microjoules(7_200_000_000), Joules(7200.0));
+        assert_eq!(Joules(7200.0).microjoules(), 7_200_000_000);
+        assert_eq!(Joules(7200.0).watt_hours(), 2.0);
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Celsius(45.5).to_string(), "45.5 °C");
+        assert_eq!(format!("{:.2}", Celsius(-40.0)), "-40.00 °C");
+        assert_eq!(Rpm(1200).to_string(), "1200 RPM");
+        assert_eq!(Volts(1.2).to_string(), "1.200 V");
+        assert_eq!(format!("{:.1}", Volts(1.25)), "1.2 V");
+        assert_eq!(Amps(-1.25).to_string(), "-1.250 A");
+        assert_eq!(Watts(12.5).to_string(), "12.50 W");
+        assert_eq!(format!("{:.0}", Watts(12.5)), "12 W");
+        assert_eq!(Joules(3600.0).to_string(), "3600.000 J");
+    }
+}
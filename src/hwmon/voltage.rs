@@ -0,0 +1,37 @@
+use crate::{HwMon, HwMonAlarms, HwMonSensor, Volts};
+use std::io::Result;
+
+hwmon_sensor!(
+    /// A voltage sensor, `inN`, with values in millivolts.
+    HwMonVoltage, "in", |s| s.input()
+);
+
+impl<'a> HwMonVoltage<'a> {
+    hwmon_items! {
+        /// Measured value, in millivolts.
+        input -> i32;
+        min -> i32;
+        max -> i32;
+        lcrit -> i32;
+        crit -> i32;
+        average -> i32;
+        lowest -> i32;
+        highest -> i32;
+        rated_min -> i32;
+        rated_max -> i32;
+    }
+
+    hwmon_scaled! {
+        Volts::from_millivolts;
+        input => input_volts;
+        min => min_volts;
+        max => max_volts;
+        lcrit => lcrit_volts;
+        crit => crit_volts;
+        average => average_volts;
+        lowest => lowest_volts;
+        highest => highest_volts;
+        rated_min => rated_min_volts;
+        rated_max => rated_max_volts;
+    }
+}
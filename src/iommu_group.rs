@@ -0,0 +1,368 @@
+use crate::{PciDevice, SysClass};
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Drivers which leave a device isolated from the host, ready for assignment to a VM.
+const VFIO_DRIVERS: &[&str] = &["vfio-pci", "pci-stub"];
+
+/// PCI-to-PCI bridge class codes, without the programming interface.
+const PCI_CLASS_BRIDGE_PCI: u32 = 0x0604;
+const PCI_CLASS_BRIDGE_PCI_SEMI_TRANSPARENT: u32 = 0x0609;
+
+/// A group of devices which the IOMMU cannot isolate from each other, in
+/// /sys/kernel/iommu_groups
+#[derive(Clone, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub struct IommuGroup {
+    path: PathBuf,
+}
+
+impl SysClass for IommuGroup {
+    fn base() -> &'static str {
+        "kernel"
+    }
+
+    fn class() -> &'static str {
+        "iommu_groups"
+    }
+
+    unsafe fn from_path_unchecked(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl IommuGroup {
+    method!("type", type_ trim_file String);
+
+    /// Every device in the group, in order of their names. Devices on other buses than PCI,
+    /// such as platform devices, are included as `IommuGroupDevice::Other`.
+    pub fn devices(&self) -> Result<Vec<IommuGroupDevice>> {
+        let mut devices = Vec::new();
+        for entry in fs::read_dir(self.path.join("devices"))? {
+            let path = fs::canonicalize(entry?.path())?;
+            let subsystem = fs::read_link(path.join("subsystem")).ok();
+            let is_pci = subsystem
+                .as_deref()
+                .and_then(Path::file_name)
+                .is_some_and(|name| name == "pci");
+
+            devices.push(if is_pci {
+                IommuGroupDevice::Pci(unsafe { PciDevice::from_path_unchecked(path) })
+            } else {
+                IommuGroupDevice::Other(path)
+            });
+        }
+
+        devices.sort_unstable_by(|a, b| a.name().cmp(b.name()));
+        Ok(devices)
+    }
+
+    /// Address ranges which cannot be used for DMA mappings by devices in the group.
+    pub fn reserved_regions(&self) -> Result<Vec<IommuReservedRegion>> {
+        self.read_file("reserved_regions")?
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(str::parse)
+            .collect()
+    }
+
+    /// Checks whether the group can be assigned to a VM through VFIO.
+    ///
+    /// Every device in the group must be a PCI device bound to `vfio-pci` or `pci-stub`.
+    /// Unbound devices are reported too, as a host driver could bind to them at any time.
+    /// PCI bridges are not assigned, and are excluded from the check.
+    pub fn vfio_readiness(&self) -> Result<VfioReadiness> {
+        let mut blockers = Vec::new();
+        for device in self.devices()? {
+            let device = match device {
+                IommuGroupDevice::Pci(device) => device,
+                IommuGroupDevice::Other(_) => {
+                    blockers.push(VfioBlocker {
+                        device: device.name().to_owned(),
+                        reason: VfioBlockReason::NotPci,
+                    });
+                    continue;
+                }
+            };
+
+            let class = device.class()? >> 8;
+            if class == PCI_CLASS_BRIDGE_PCI || class == PCI_CLASS_BRIDGE_PCI_SEMI_TRANSPARENT {
+                continue;
+            }
+
+            let reason = match device.driver() {
+                Ok(ref driver) if VFIO_DRIVERS.contains(&driver.id()) => continue,
+                Ok(driver) => VfioBlockReason::Driver(driver.id().to_owned()),
+                Err(ref why) if why.kind() == ErrorKind::NotFound => VfioBlockReason::Unbound,
+                Err(why) => return Err(why),
+            };
+
+            blockers.push(VfioBlocker {
+                device: device.id().to_owned(),
+                reason,
+            });
+        }
+
+        Ok(VfioReadiness {
+            group: self.id().to_owned(),
+            blockers,
+        })
+    }
+}
+
+/// A device in an IOMMU group.
+#[derive(Clone, Debug)]
+pub enum IommuGroupDevice {
+    Pci(PciDevice),
+    /// A device on another bus, by its path in /sys/devices.
+    Other(PathBuf),
+}
+
+impl IommuGroupDevice {
+    /// The name of the device, such as `0000:01:00.0` for a PCI device.
+    pub fn name(&self) -> &str {
+        let path = match self {
+            IommuGroupDevice::Pci(device) => device.path(),
+            IommuGroupDevice::Other(path) => path,
+        };
+
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default()
+    }
+}
+
+impl PciDevice {
+    pub fn iommu_group(&self) -> Result<IommuGroup> {
+        let path = fs::canonicalize(self.path().join("iommu_group"))?;
+        match path.file_name().and_then(|name| name.to_str()) {
+            Some(_) => Ok(unsafe { IommuGroup::from_path_unchecked(path) }),
+            None => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("{}: invalid link", path.display()),
+            )),
+        }
+    }
+
+    /// Checks whether the IOMMU group of this device can be assigned to a VM through VFIO.
+    pub fn vfio_readiness(&self) -> Result<VfioReadiness> {
+        self.iommu_group()?.vfio_readiness()
+    }
+}
+
+/// A reserved address range of an IOMMU group.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IommuReservedRegion {
+    pub start: u64,
+    pub end: u64,
+    /// The kind of region, such as `direct`, `reserved` or `msi`.
+    pub kind: String,
+}
+
+impl FromStr for IommuReservedRegion {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::new(ErrorKind::InvalidData, format!("reserved region: {}", s));
+        let parse = |value: Option<&str>| {
+            value
+                .and_then(|value| value.strip_prefix("0x"))
+                .and_then(|value| u64::from_str_radix(value, 16).ok())
+                .ok_or_else(invalid)
+        };
+
+        let mut fields = s.split_whitespace();
+        Ok(IommuReservedRegion {
+            start: parse(fields.next())?,
+            end: parse(fields.next())?,
+            kind: fields.next().ok_or_else(invalid)?.to_owned(),
+        })
+    }
+}
+
+/// Why a device prevents its IOMMU group from being assigned through VFIO.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum VfioBlockReason {
+    /// Bound to a host driver, which is named.
+    Driver(String),
+    /// Not bound to any driver.
+    Unbound,
+    /// Not a PCI device, so it cannot be bound to a VFIO PCI driver.
+    NotPci,
+}
+
+/// A device which prevents an IOMMU group from being assigned through VFIO.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VfioBlocker {
+    pub device: String,
+    pub reason: VfioBlockReason,
+}
+
+/// The result of checking an IOMMU group for VFIO assignment.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VfioReadiness {
+    pub group: String,
+    pub blockers: Vec<VfioBlocker>,
+}
+
+impl VfioReadiness {
+    /// All devices in the group are isolated from the host.
+    pub fn is_ready(&self) -> bool {
+        self.blockers.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use std::os::unix::fs::symlink;
+
+    struct Fixture {
+        dir: TempDir,
+    }
+
+    impl Fixture {
+        fn new(name: &str) -> Self {
+            let dir = TempDir::new(name);
+            for path in &[
+                "bus/pci",
+                "bus/platform",
+                "drivers/vfio-pci",
+                "drivers/nvme",
+            ] {
+                fs::create_dir_all(dir.path().join(path)).unwrap();
+            }
+            fs::create_dir_all(dir.path().join("groups/7/devices")).unwrap();
+            Fixture { dir }
+        }
+
+        fn add(&self, name: &str, bus: &str, class: u32, driver: Option<&str>) -> PathBuf {
+            let root = self.dir.path();
+            let path = root.join("devices").join(name);
+            fs::create_dir_all(&path).unwrap();
+            symlink(root.join("bus").join(bus), path.join("subsystem")).unwrap();
+            symlink(root.join("groups/7"), path.join("iommu_group")).unwrap();
+            fs::write(path.join("class"), format!("0x{:06x}\n", class)).unwrap();
+            if let Some(driver) = driver {
+                symlink(root.join("drivers").join(driver), path.join("driver")).unwrap();
+            }
+
+            symlink(&path, root.join("groups/7/devices").join(name)).unwrap();
+            path
+        }
+
+        fn group(&self) -> IommuGroup {
+            unsafe { IommuGroup::from_path_unchecked(self.dir.path().join("groups/7")) }
+        }
+    }
+
+    #[test]
+    fn ready() {
+        let fixture = Fixture::new("iommu-ready");
+        let gpu = fixture.add("0000:01:00.0", "pci", 0x030000, Some("vfio-pci"));
+        fixture.add("0000:01:00.1", "pci", 0x040300, Some("vfio-pci"));
+        // Bridges are excluded whether or not they are bound.
+        fixture.add("0000:00:01.0", "pci", 0x060400, None);
+        fixture.add("0000:00:01.1", "pci", 0x060400, Some("nvme"));
+
+        let readiness = fixture.group().vfio_readiness().unwrap();
+        assert_eq!(readiness.group, "7");
+        assert!(readiness.is_ready(), "{:?}", readiness.blockers);
+
+        let device = unsafe { PciDevice::from_path_unchecked(gpu) };
+        assert_eq!(device.iommu_group().unwrap().id(), "7");
+        assert!(device.vfio_readiness().unwrap().is_ready());
+    }
+
+    #[test]
+    fn blockers() {
+        let fixture = Fixture::new("iommu-blockers");
+        fixture.add("0000:01:00.0", "pci", 0x030000, Some("vfio-pci"));
+        fixture.add("0000:01:00.1", "pci", 0x040300, None);
+        fixture.add("0000:02:00.0", "pci", 0x010802, Some("nvme"));
+        fixture.add("ahci.0", "platform", 0, None);
+
+        let names = fixture
+            .group()
+            .devices()
+            .unwrap()
+            .iter()
+            .map(|device| {
+                (
+                    device.name().to_owned(),
+                    matches!(device, IommuGroupDevice::Pci(_)),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                ("0000:01:00.0".to_owned(), true),
+                ("0000:01:00.1".to_owned(), true),
+                ("0000:02:00.0".to_owned(), true),
+                ("ahci.0".to_owned(), false),
+            ]
+        );
+
+        let readiness = fixture.group().vfio_readiness().unwrap();
+        assert!(!readiness.is_ready());
+        assert_eq!(
+            readiness.blockers,
+            [
+                VfioBlocker {
+                    device: "0000:01:00.1".into(),
+                    reason: VfioBlockReason::Unbound,
+                },
+                VfioBlocker {
+                    device: "0000:02:00.0".into(),
+                    reason: VfioBlockReason::Driver("nvme".into()),
+                },
+                VfioBlocker {
+                    device: "ahci.0".into(),
+                    reason: VfioBlockReason::NotPci,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reserved_regions() {
+        let region: IommuReservedRegion =
+            "0x00000000fee00000 0x00000000feefffff msi".parse().unwrap();
+        assert_eq!(
+            region,
+            IommuReservedRegion {
+                start: 0xfee0_0000,
+                end: 0xfeef_ffff,
+                kind: "msi".into(),
+            }
+        );
+
+        for line in &[
+            "",
+            "0xfee00000 0xfeefffff",
+            "fee00000 0xfeefffff msi",
+            "0xfee00000 0xzz msi",
+        ] {
+            let err = line.parse::<IommuReservedRegion>().unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidData);
+        }
+
+        let fixture = Fixture::new("iommu-reserved");
+        fs::write(
+            fixture.dir.path().join("groups/7/reserved_regions"),
+            "0x00000000000a0000 0x00000000000bffff direct\n\
+             0x00000000fee00000 0x00000000feefffff msi\n",
+        )
+        .unwrap();
+        let regions = fixture.group().reserved_regions().unwrap();
+        assert_eq!(regions.len(), 2);
+        assert_eq!(regions[0].kind, "direct");
+        assert_eq!(regions[1].start, 0xfee0_0000);
+    }
+}
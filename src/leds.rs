@@ -32,7 +32,7 @@ impl Leds {
             object
                 .as_ref()
                 .ok()
-                .map_or(true, |o| o.id().contains("kbd_backlight"))
+                .is_none_or(|o| o.id().contains("kbd_backlight"))
         })
     }
 }
@@ -8,26 +8,53 @@ mod backlight;
 pub use crate::brightness::Brightness;
 mod brightness;
 
-pub use crate::block::Block;
+pub use crate::block::{
+    Block, BlockDeviceType, ByteSize, DiscardSupport, LoopControl, LoopOptions, PartitionAlignment,
+    PartitionTableScheme, ScsiDeviceType, Zram, ZramBdStat, ZramCompAlgorithm, ZramControl,
+    ZramIoStat, ZramMmStat, ZramWriteback,
+};
 mod block;
 
 pub use crate::dmi::DmiId;
 mod dmi;
 
-pub use crate::hwmon::{HwMon, HwMonFan, HwMonPwm, HwMonTemp};
+pub use crate::hwmon::{
+    Amps, Celsius, Clock, FanControlOptions, FanController, FanCurve, HwMon, HwMonActiveAlarms,
+    HwMonAlarm, HwMonAlarms, HwMonCurrent, HwMonEnergy, HwMonFan, HwMonHumidity, HwMonIntrusion,
+    HwMonPower, HwMonPwm, HwMonPwmAutoPoint, HwMonPwmEnable, HwMonPwmMode, HwMonSensor,
+    HwMonStableId, HwMonTemp, HwMonVoltage, Joules, ManualClock, Rpm, SensorsConfig, SystemClock,
+    Volts, Watts,
+};
 mod hwmon;
 
+pub use crate::iommu_group::{
+    IommuGroup, IommuGroupDevice, IommuReservedRegion, VfioBlockReason, VfioBlocker, VfioReadiness,
+};
+mod iommu_group;
+
 pub use crate::leds::Leds;
 mod leds;
 
-pub use crate::net::Net;
+pub use crate::net::{Net, NetCounters, NetRateSampler, NetRates, NetStatistics};
 mod net;
 
-pub use crate::pci_bus::{PciDevice, PciDriver};
+pub use crate::pci_bus::{
+    PciAcs, PciAddress, PciAer, PciAerCounters, PciBar, PciBarKind, PciCapability,
+    PciCapabilityKind, PciCommand, PciConfig, PciDevice, PciDpc, PciDriver, PciExpress,
+    PciExpressPortType, PciExtCapability, PciExtCapabilityKind, PciHeaderType, PciL1Substates,
+    PciLinkSpeed, PciLinkStatus, PciMsi, PciMsiX, PciPowerManagement, PciPowerState, PciRebind,
+    PciResource, PciResourceMap, PciRomCodeType, PciRomImage, PciRootBus, PciSlot, PciSrIov,
+    PciStatus, PciTopology, PciTopologyNode, PciVpd, PciVpdField,
+};
+#[cfg(feature = "pci-ids")]
+pub use crate::pci_bus::{PciDeviceNames, PciIds};
 mod pci_bus;
 
-pub use crate::runtime_pm::{RuntimePM, RuntimePowerManagement};
+pub use crate::runtime_pm::{RuntimePM, RuntimePMExt, RuntimePowerManagement, RuntimeStatus};
 mod runtime_pm;
 
 pub use crate::scsi_host::ScsiHost;
 mod scsi_host;
+
+#[cfg(test)]
+mod test_util;
@@ -1,6 +1,7 @@
 use crate::SysClass;
-use std::io::Result;
+use std::io::{ErrorKind, Result};
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 #[derive(Clone)]
 pub struct Net {
@@ -22,7 +23,7 @@ impl SysClass for Net {
 }
 
 impl Net {
-    pub fn statistics(&self) -> NetStatistics {
+    pub fn statistics(&self) -> NetStatistics<'_> {
         NetStatistics { parent: self }
     }
 
@@ -44,26 +45,292 @@ impl Net {
     method!(tx_queue_len parse_file u32);
 }
 
+macro_rules! net_counters {
+    ($( $(#[$doc:meta])* $counter:ident, )*) => {
+        /// Every counter of `statistics`, read at once by `NetStatistics::counters`.
+        #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+        pub struct NetCounters {
+            $( $(#[$doc])* pub $counter: u64, )*
+        }
+
+        /// Per-second rates of every counter, from `NetRateSampler`.
+        #[derive(Clone, Copy, Debug, Default, PartialEq)]
+        pub struct NetRates {
+            $( $(#[$doc])* pub $counter: f64, )*
+        }
+
+        impl<'a> NetStatistics<'a> {
+            $(
+                $(#[$doc])*
+                pub fn $counter(&self) -> Result<u64> {
+                    self.parent.parse_file([Self::DIR, "/", stringify!($counter)].concat())
+                }
+            )*
+
+            /// Reads every counter. `rx_nohandler`, which kernels before Linux 4.6 do not
+            /// have, is zero if it is missing, but any other missing counter is an error.
+            pub fn counters(&self) -> Result<NetCounters> {
+                let read = |name: &str, counter: Result<u64>| match counter {
+                    Err(why) if why.kind() == ErrorKind::NotFound && name == "rx_nohandler" => {
+                        Ok(0)
+                    }
+                    counter => counter,
+                };
+
+                Ok(NetCounters {
+                    $( $counter: read(stringify!($counter), self.$counter())?, )*
+                })
+            }
+        }
+
+        impl NetCounters {
+            /// The increase of every counter since `previous`, taking counter wrap into
+            /// account.
+            pub fn delta(&self, previous: &NetCounters) -> NetCounters {
+                NetCounters {
+                    $( $counter: counter_delta(previous.$counter, self.$counter), )*
+                }
+            }
+
+            /// The rates of every counter between `previous` and these counters, which
+            /// were read `elapsed` apart.
+            pub fn rates(&self, previous: &NetCounters, elapsed: Duration) -> NetRates {
+                let delta = self.delta(previous);
+                let secs = elapsed.as_secs_f64();
+                let rate = |delta: u64| if secs > 0.0 { delta as f64 / secs } else { 0.0 };
+                NetRates {
+                    $( $counter: rate(delta.$counter), )*
+                }
+            }
+        }
+    }
+}
+
+/// How close to `u32::MAX` a counter must have been, and how close to zero it must be
+/// now, for a backward step to count as a 32-bit wrap rather than a reset.
+const WRAP_WINDOW: u64 = 1 << 30;
+
+/// The increase of a counter from `previous` to `current`.
+///
+/// Some drivers only keep 32-bit counters, so a counter which was near `u32::MAX` and is
+/// now near zero is taken to have wrapped. Any other backward step is taken to be a
+/// reset, such as from reloading the driver, and the increase is the new value.
+fn counter_delta(previous: u64, current: u64) -> u64 {
+    let max = u64::from(u32::MAX);
+    if current >= previous {
+        current - previous
+    } else if previous <= max && max - previous < WRAP_WINDOW && current < WRAP_WINDOW {
+        (max - previous) + current + 1
+    } else {
+        current
+    }
+}
+
 pub struct NetStatistics<'a> {
     parent: &'a Net,
 }
 
 impl<'a> NetStatistics<'a> {
     const DIR: &'static str = "statistics";
+}
+
+net_counters! {
+    rx_bytes,
+    rx_packets,
+    /// Receive errors of every kind, including those counted separately below.
+    rx_errors,
+    /// Packets dropped by the stack, such as for lack of memory or an unknown protocol.
+    rx_dropped,
+    rx_fifo_errors,
+    rx_frame_errors,
+    rx_crc_errors,
+    rx_length_errors,
+    rx_over_errors,
+    /// Packets missed by the device, such as for lack of receive buffers.
+    rx_missed_errors,
+    rx_compressed,
+    /// Packets dropped because no protocol handler was registered for them.
+    rx_nohandler,
+    multicast,
+    tx_bytes,
+    tx_packets,
+    /// Transmit errors of every kind, including those counted separately below.
+    tx_errors,
+    tx_dropped,
+    tx_fifo_errors,
+    tx_aborted_errors,
+    tx_carrier_errors,
+    tx_window_errors,
+    tx_heartbeat_errors,
+    tx_compressed,
+    collisions,
+}
+
+/// Samples the counters of an interface to compute their per-second rates.
+pub struct NetRateSampler {
+    net: Net,
+    previous: Option<(Instant, NetCounters)>,
+}
+
+impl NetRateSampler {
+    pub fn new(net: Net) -> Self {
+        Self {
+            net,
+            previous: None,
+        }
+    }
+
+    pub fn net(&self) -> &Net {
+        &self.net
+    }
+
+    /// Reads the counters, and returns their rates since the previous sample. The first
+    /// sample has no rates.
+    pub fn sample(&mut self) -> Result<Option<NetRates>> {
+        let counters = self.net.statistics().counters()?;
+        let now = Instant::now();
+        let rates = self
+            .previous
+            .map(|(then, previous)| counters.rates(&previous, now.duration_since(then)));
+
+        self.previous = Some((now, counters));
+        Ok(rates)
+    }
 
-    pub fn rx_bytes(&self) -> Result<u64> {
-        self.parent.parse_file(&[Self::DIR, "/rx_bytes"].concat())
+    /// Forgets the previous sample, such as after the interface was recreated.
+    pub fn reset(&mut self) {
+        self.previous = None;
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use std::fs;
+
+    #[test]
+    fn delta() {
+        assert_eq!(counter_delta(0, 0), 0);
+        assert_eq!(counter_delta(100, 250), 150);
+        assert_eq!(counter_delta(u64::MAX - 1, u64::MAX), 1);
+
+        // 32-bit counters which wrapped.
+        assert_eq!(counter_delta(u64::from(u32::MAX), 0), 1);
+        assert_eq!(counter_delta(u64::from(u32::MAX) - 9, 10), 20);
+        assert_eq!(counter_delta(0xf000_0000, 0x1000_0000), 0x2000_0000);
 
-    pub fn rx_packets(&self) -> Result<u64> {
-        self.parent.parse_file(&[Self::DIR, "/rx_packets"].concat())
+        // Resets, rather than wraps.
+        assert_eq!(counter_delta(5, 0), 0);
+        assert_eq!(counter_delta(5, 2), 2);
+        assert_eq!(counter_delta(0x8000_0000, 10), 10);
+        assert_eq!(counter_delta(u64::from(u32::MAX), 0x8000_0000), 0x8000_0000);
+        assert_eq!(counter_delta(1 << 40, 7), 7);
+        assert_eq!(counter_delta(u64::MAX, 3), 3);
     }
 
-    pub fn tx_bytes(&self) -> Result<u64> {
-        self.parent.parse_file(&[Self::DIR, "/tx_bytes"].concat())
+    #[test]
+    fn rates() {
+        let previous = NetCounters {
+            rx_bytes: 1_000,
+            tx_bytes: u64::from(u32::MAX) - 499,
+            rx_errors: 5,
+            rx_dropped: 10,
+            ..NetCounters::default()
+        };
+        let current = NetCounters {
+            rx_bytes: 5_000,
+            tx_bytes: 1_500,
+            rx_errors: 0,
+            rx_dropped: 30,
+            ..NetCounters::default()
+        };
+
+        let delta = current.delta(&previous);
+        assert_eq!(delta.rx_bytes, 4_000);
+        assert_eq!(delta.tx_bytes, 2_000);
+        assert_eq!(delta.rx_errors, 0);
+        assert_eq!(delta.rx_dropped, 20);
+
+        let rates = current.rates(&previous, Duration::from_secs(2));
+        assert_eq!(rates.rx_bytes, 2_000.0);
+        assert_eq!(rates.tx_bytes, 1_000.0);
+        assert_eq!(rates.rx_errors, 0.0);
+        assert_eq!(rates.rx_dropped, 10.0);
+        assert_eq!(rates.collisions, 0.0);
+
+        let rates = current.rates(&previous, Duration::from_millis(500));
+        assert_eq!(rates.rx_dropped, 40.0);
+
+        // No time has passed, so there is no rate to report.
+        let rates = current.rates(&previous, Duration::from_secs(0));
+        assert_eq!(rates, NetRates::default());
+    }
+
+    #[test]
+    fn counters() {
+        let dir = TempDir::new("net-counters");
+        let statistics = dir.path().join("statistics");
+        fs::create_dir(&statistics).unwrap();
+        for counter in &[
+            "rx_packets",
+            "tx_packets",
+            "rx_errors",
+            "tx_errors",
+            "rx_dropped",
+            "tx_dropped",
+            "multicast",
+            "collisions",
+            "rx_length_errors",
+            "rx_over_errors",
+            "rx_crc_errors",
+            "rx_frame_errors",
+            "rx_fifo_errors",
+            "rx_missed_errors",
+            "tx_aborted_errors",
+            "tx_carrier_errors",
+            "tx_fifo_errors",
+            "tx_heartbeat_errors",
+            "tx_window_errors",
+            "rx_compressed",
+            "tx_compressed",
+        ] {
+            fs::write(statistics.join(counter), "0\n").unwrap();
+        }
+        for (file, value) in &[
+            ("rx_bytes", "123456\n"),
+            ("tx_bytes", "654321\n"),
+            ("rx_dropped", "7\n"),
+            ("tx_carrier_errors", "2\n"),
+        ] {
+            fs::write(statistics.join(file), value).unwrap();
+        }
+
+        // rx_nohandler, which old kernels do not have, reads as zero.
+        let net = unsafe { Net::from_path_unchecked(dir.path().to_owned()) };
+        let counters = net.statistics().counters().unwrap();
+        assert_eq!(counters.rx_bytes, 123_456);
+        assert_eq!(counters.tx_bytes, 654_321);
+        assert_eq!(counters.rx_dropped, 7);
+        assert_eq!(counters.tx_carrier_errors, 2);
+        assert_eq!(counters.rx_nohandler, 0);
+        assert!(net.statistics().rx_nohandler().is_err());
+
+        fs::write(statistics.join("rx_errors"), "many\n").unwrap();
+        assert!(net.statistics().counters().is_err());
+
+        // Other counters are not optional.
+        fs::write(statistics.join("rx_errors"), "0\n").unwrap();
+        fs::remove_file(statistics.join("tx_dropped")).unwrap();
+        let err = net.statistics().counters().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
     }
 
-    pub fn tx_packets(&self) -> Result<u64> {
-        self.parent.parse_file(&[Self::DIR, "/tx_packets"].concat())
+    #[test]
+    fn counters_without_statistics() {
+        let dir = TempDir::new("net-no-statistics");
+        let net = unsafe { Net::from_path_unchecked(dir.path().to_owned()) };
+        let err = net.statistics().counters().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
     }
 }
@@ -0,0 +1,1515 @@
+// Offsets and identifiers in configuration space. Copied from the kernel header.
+// https://git.kernel.org/pub/scm/linux/kernel/git/torvalds/linux.git/tree/include/uapi/linux/pci_regs.h
+const PCI_VENDOR_ID: usize = 0x00;
+const PCI_DEVICE_ID: usize = 0x02;
+const PCI_COMMAND: usize = 0x04;
+const PCI_STATUS: usize = 0x06;
+const PCI_CLASS_REVISION: usize = 0x08;
+const PCI_CACHE_LINE_SIZE: usize = 0x0c;
+const PCI_LATENCY_TIMER: usize = 0x0d;
+const PCI_HEADER_TYPE: usize = 0x0e;
+const PCI_BASE_ADDRESS_0: usize = 0x10;
+const PCI_PRIMARY_BUS: usize = 0x18;
+const PCI_SECONDARY_BUS: usize = 0x19;
+const PCI_SUBORDINATE_BUS: usize = 0x1a;
+const PCI_SUBSYSTEM_VENDOR_ID: usize = 0x2c;
+const PCI_SUBSYSTEM_ID: usize = 0x2e;
+const PCI_CAPABILITY_LIST: usize = 0x34;
+const PCI_INTERRUPT_LINE: usize = 0x3c;
+const PCI_INTERRUPT_PIN: usize = 0x3d;
+
+const PCI_STATUS_CAP_LIST: u16 = 0x10;
+
+const PCI_CFG_SPACE_SIZE: usize = 256;
+const PCI_CFG_SPACE_EXP_SIZE: usize = 4096;
+
+const PCI_CAP_ID_PM: u8 = 0x01;
+const PCI_CAP_ID_MSI: u8 = 0x05;
+const PCI_CAP_ID_VNDR: u8 = 0x09;
+const PCI_CAP_ID_EXP: u8 = 0x10;
+const PCI_CAP_ID_MSIX: u8 = 0x11;
+
+const PCI_EXT_CAP_ID_ERR: u16 = 0x0001;
+const PCI_EXT_CAP_ID_DSN: u16 = 0x0003;
+const PCI_EXT_CAP_ID_VNDR: u16 = 0x000b;
+const PCI_EXT_CAP_ID_ACS: u16 = 0x000d;
+const PCI_EXT_CAP_ID_ARI: u16 = 0x000e;
+const PCI_EXT_CAP_ID_SRIOV: u16 = 0x0010;
+const PCI_EXT_CAP_ID_REBAR: u16 = 0x0015;
+const PCI_EXT_CAP_ID_LTR: u16 = 0x0018;
+const PCI_EXT_CAP_ID_DPC: u16 = 0x001d;
+const PCI_EXT_CAP_ID_L1SS: u16 = 0x001e;
+
+/// Upper bounds on the capability lists, which protect against loops in broken devices.
+const MAX_CAPABILITIES: usize = (PCI_CFG_SPACE_SIZE - 0x40) / 4;
+const MAX_EXT_CAPABILITIES: usize = (PCI_CFG_SPACE_EXP_SIZE - PCI_CFG_SPACE_SIZE) / 8;
+
+/// The configuration space of a PCI device, as read from its `config` file.
+///
+/// Unprivileged readers only receive the first 64 bytes, so fields beyond the standard
+/// header, including all capabilities, are unavailable to them.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PciConfig {
+    data: Vec<u8>,
+}
+
+impl PciConfig {
+    pub fn from_bytes(data: Vec<u8>) -> Self {
+        Self { data }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn read_u8(&self, offset: usize) -> Option<u8> {
+        self.data.get(offset).copied()
+    }
+
+    pub fn read_u16(&self, offset: usize) -> Option<u16> {
+        let bytes = self.data.get(offset..offset.checked_add(2)?)?;
+        Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub fn read_u32(&self, offset: usize) -> Option<u32> {
+        let bytes = self.data.get(offset..offset.checked_add(4)?)?;
+        Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    pub fn read_u64(&self, offset: usize) -> Option<u64> {
+        let low = self.read_u32(offset)? as u64;
+        let high = self.read_u32(offset.checked_add(4)?)? as u64;
+        Some(high << 32 | low)
+    }
+
+    // Standard header
+
+    pub fn vendor_id(&self) -> Option<u16> {
+        self.read_u16(PCI_VENDOR_ID)
+    }
+
+    pub fn device_id(&self) -> Option<u16> {
+        self.read_u16(PCI_DEVICE_ID)
+    }
+
+    pub fn command(&self) -> Option<PciCommand> {
+        self.read_u16(PCI_COMMAND).map(PciCommand)
+    }
+
+    pub fn status(&self) -> Option<PciStatus> {
+        self.read_u16(PCI_STATUS).map(PciStatus)
+    }
+
+    pub fn revision(&self) -> Option<u8> {
+        self.read_u8(PCI_CLASS_REVISION)
+    }
+
+    /// The 24-bit class code, made of the class, subclass and programming interface.
+    pub fn class(&self) -> Option<u32> {
+        self.read_u32(PCI_CLASS_REVISION).map(|value| value >> 8)
+    }
+
+    pub fn cache_line_size(&self) -> Option<u8> {
+        self.read_u8(PCI_CACHE_LINE_SIZE)
+    }
+
+    pub fn latency_timer(&self) -> Option<u8> {
+        self.read_u8(PCI_LATENCY_TIMER)
+    }
+
+    pub fn header_type(&self) -> Option<PciHeaderType> {
+        self.read_u8(PCI_HEADER_TYPE)
+            .map(|value| PciHeaderType::from(value & 0x7f))
+    }
+
+    pub fn is_multifunction(&self) -> Option<bool> {
+        self.read_u8(PCI_HEADER_TYPE).map(|value| value & 0x80 != 0)
+    }
+
+    /// Base address registers, decoded from the header. Sizes are not available from
+    /// configuration space without probing the device.
+    pub fn bars(&self) -> Vec<PciBar> {
+        let count = match self.header_type() {
+            Some(PciHeaderType::Normal) => 6,
+            Some(PciHeaderType::Bridge) => 2,
+            _ => 0,
+        };
+
+        let mut bars = Vec::new();
+        let mut index = 0;
+        while index < count {
+            let offset = PCI_BASE_ADDRESS_0 + index * 4;
+            let value = match self.read_u32(offset) {
+                Some(value) => value,
+                None => break,
+            };
+
+            let bar = if value & 0x1 != 0 {
+                PciBar {
+                    index: index as u8,
+                    address: (value & !0x3) as u64,
+                    kind: PciBarKind::Io,
+                    prefetchable: false,
+                }
+            } else if (value >> 1) & 0x3 == 0x2 {
+                let high = self.read_u32(offset + 4).unwrap_or(0) as u64;
+                let bar = PciBar {
+                    index: index as u8,
+                    address: high << 32 | (value & !0xf) as u64,
+                    kind: PciBarKind::Memory64,
+                    prefetchable: value & 0x8 != 0,
+                };
+                // The upper half of the address occupies the next register.
+                index += 1;
+                bar
+            } else {
+                PciBar {
+                    index: index as u8,
+                    address: (value & !0xf) as u64,
+                    kind: PciBarKind::Memory32,
+                    prefetchable: value & 0x8 != 0,
+                }
+            };
+
+            if value != 0 {
+                bars.push(bar);
+            }
+
+            index += 1;
+        }
+
+        bars
+    }
+
+    pub fn subsystem_vendor_id(&self) -> Option<u16> {
+        match self.header_type()? {
+            PciHeaderType::Normal => self.read_u16(PCI_SUBSYSTEM_VENDOR_ID),
+            _ => None,
+        }
+    }
+
+    pub fn subsystem_id(&self) -> Option<u16> {
+        match self.header_type()? {
+            PciHeaderType::Normal => self.read_u16(PCI_SUBSYSTEM_ID),
+            _ => None,
+        }
+    }
+
+    /// Primary, secondary and subordinate bus numbers of a bridge.
+    pub fn bridge_buses(&self) -> Option<(u8, u8, u8)> {
+        match self.header_type()? {
+            PciHeaderType::Bridge => Some((
+                self.read_u8(PCI_PRIMARY_BUS)?,
+                self.read_u8(PCI_SECONDARY_BUS)?,
+                self.read_u8(PCI_SUBORDINATE_BUS)?,
+            )),
+            _ => None,
+        }
+    }
+
+    pub fn interrupt_line(&self) -> Option<u8> {
+        self.read_u8(PCI_INTERRUPT_LINE)
+    }
+
+    /// The legacy interrupt pin used by the device, where 1 to 4 are INTA# to INTD#, and
+    /// 0 means that no pin is used.
+    pub fn interrupt_pin(&self) -> Option<u8> {
+        self.read_u8(PCI_INTERRUPT_PIN)
+    }
+
+    // Capabilities
+
+    /// Walks the classic capability list.
+    pub fn capabilities(&self) -> Vec<PciCapability> {
+        let mut capabilities = Vec::new();
+        if self
+            .status()
+            .is_none_or(|status| !status.capabilities_list())
+        {
+            return capabilities;
+        }
+
+        let mut offset = self.read_u8(PCI_CAPABILITY_LIST).unwrap_or(0) & !0x3;
+        while offset >= 0x40 && capabilities.len() < MAX_CAPABILITIES {
+            let pos = offset as usize;
+            let (id, next) = match (self.read_u8(pos), self.read_u8(pos + 1)) {
+                (Some(id), Some(next)) => (id, next),
+                _ => break,
+            };
+
+            let kind = match id {
+                PCI_CAP_ID_PM => {
+                    self.read_u16(pos + 2)
+                        .zip(self.read_u16(pos + 4))
+                        .map(|(pmc, pmcsr)| {
+                            PciCapabilityKind::PowerManagement(PciPowerManagement { pmc, pmcsr })
+                        })
+                }
+                PCI_CAP_ID_MSI => self.msi(pos).map(PciCapabilityKind::Msi),
+                PCI_CAP_ID_VNDR => Some(PciCapabilityKind::VendorSpecific),
+                PCI_CAP_ID_EXP => self.express(pos).map(PciCapabilityKind::PciExpress),
+                PCI_CAP_ID_MSIX => self.msix(pos).map(PciCapabilityKind::MsiX),
+                _ => None,
+            };
+
+            capabilities.push(PciCapability {
+                id,
+                offset,
+                kind: kind.unwrap_or(PciCapabilityKind::Other),
+            });
+
+            offset = next & !0x3;
+        }
+
+        capabilities
+    }
+
+    /// Walks the extended capability list of PCI Express devices.
+    pub fn extended_capabilities(&self) -> Vec<PciExtCapability> {
+        let mut capabilities = Vec::new();
+
+        let mut offset = PCI_CFG_SPACE_SIZE as u16;
+        while capabilities.len() < MAX_EXT_CAPABILITIES {
+            let pos = offset as usize;
+            let header = match self.read_u32(pos) {
+                Some(header) if header != 0 && header != 0xffff_ffff => header,
+                _ => break,
+            };
+
+            let id = header as u16;
+            let kind = match id {
+                PCI_EXT_CAP_ID_ERR => self.aer(pos).map(PciExtCapabilityKind::Aer),
+                PCI_EXT_CAP_ID_DSN => self
+                    .read_u64(pos + 4)
+                    .map(PciExtCapabilityKind::SerialNumber),
+                PCI_EXT_CAP_ID_VNDR => Some(PciExtCapabilityKind::VendorSpecific),
+                PCI_EXT_CAP_ID_ACS => self.read_u16(pos + 4).zip(self.read_u16(pos + 6)).map(
+                    |(capability, control)| {
+                        PciExtCapabilityKind::Acs(PciAcs {
+                            capability,
+                            control,
+                        })
+                    },
+                ),
+                PCI_EXT_CAP_ID_ARI => Some(PciExtCapabilityKind::Ari),
+                PCI_EXT_CAP_ID_SRIOV => self.sriov(pos).map(PciExtCapabilityKind::SrIov),
+                PCI_EXT_CAP_ID_REBAR => Some(PciExtCapabilityKind::ResizableBar),
+                PCI_EXT_CAP_ID_LTR => Some(PciExtCapabilityKind::LatencyTolerance),
+                PCI_EXT_CAP_ID_DPC => self.dpc(pos).map(PciExtCapabilityKind::Dpc),
+                PCI_EXT_CAP_ID_L1SS => self.read_u32(pos + 4).zip(self.read_u32(pos + 8)).map(
+                    |(capability, control1)| {
+                        PciExtCapabilityKind::L1Substates(PciL1Substates {
+                            capability,
+                            control1,
+                        })
+                    },
+                ),
+                _ => None,
+            };
+
+            capabilities.push(PciExtCapability {
+                id,
+                version: ((header >> 16) & 0xf) as u8,
+                offset,
+                kind: kind.unwrap_or(PciExtCapabilityKind::Other),
+            });
+
+            offset = (header >> 20) as u16 & !0x3;
+            if (offset as usize) < PCI_CFG_SPACE_SIZE {
+                break;
+            }
+        }
+
+        capabilities
+    }
+
+    /// Finds a classic capability by its identifier.
+    pub fn find_capability(&self, id: u8) -> Option<PciCapability> {
+        self.capabilities().into_iter().find(|cap| cap.id == id)
+    }
+
+    /// Finds an extended capability by its identifier.
+    pub fn find_extended_capability(&self, id: u16) -> Option<PciExtCapability> {
+        self.extended_capabilities()
+            .into_iter()
+            .find(|cap| cap.id == id)
+    }
+
+    fn msi(&self, pos: usize) -> Option<PciMsi> {
+        let control = self.read_u16(pos + 2)?;
+        let is_64bit = control & 0x80 != 0;
+        let (address, data) = if is_64bit {
+            (self.read_u64(pos + 4)?, self.read_u16(pos + 12)?)
+        } else {
+            (self.read_u32(pos + 4)? as u64, self.read_u16(pos + 8)?)
+        };
+
+        Some(PciMsi {
+            control,
+            address,
+            data,
+        })
+    }
+
+    fn msix(&self, pos: usize) -> Option<PciMsiX> {
+        Some(PciMsiX {
+            control: self.read_u16(pos + 2)?,
+            table: self.read_u32(pos + 4)?,
+            pba: self.read_u32(pos + 8)?,
+        })
+    }
+
+    fn express(&self, pos: usize) -> Option<PciExpress> {
+        Some(PciExpress {
+            capabilities: self.read_u16(pos + 2)?,
+            device_capabilities: self.read_u32(pos + 4)?,
+            device_control: self.read_u16(pos + 8)?,
+            device_status: self.read_u16(pos + 10)?,
+            link_capabilities: self.read_u32(pos + 12)?,
+            link_control: self.read_u16(pos + 16)?,
+            link_status: self.read_u16(pos + 18)?,
+        })
+    }
+
+    fn aer(&self, pos: usize) -> Option<PciAer> {
+        Some(PciAer {
+            uncorrectable_status: self.read_u32(pos + 4)?,
+            uncorrectable_mask: self.read_u32(pos + 8)?,
+            uncorrectable_severity: self.read_u32(pos + 12)?,
+            correctable_status: self.read_u32(pos + 16)?,
+            correctable_mask: self.read_u32(pos + 20)?,
+            capabilities_control: self.read_u32(pos + 24)?,
+        })
+    }
+
+    fn sriov(&self, pos: usize) -> Option<PciSrIov> {
+        Some(PciSrIov {
+            capabilities: self.read_u32(pos + 4)?,
+            control: self.read_u16(pos + 8)?,
+            status: self.read_u16(pos + 10)?,
+            initial_vfs: self.read_u16(pos + 12)?,
+            total_vfs: self.read_u16(pos + 14)?,
+            num_vfs: self.read_u16(pos + 16)?,
+            first_vf_offset: self.read_u16(pos + 20)?,
+            vf_stride: self.read_u16(pos + 22)?,
+            vf_device_id: self.read_u16(pos + 26)?,
+            supported_page_sizes: self.read_u32(pos + 28)?,
+            system_page_size: self.read_u32(pos + 32)?,
+        })
+    }
+
+    fn dpc(&self, pos: usize) -> Option<PciDpc> {
+        Some(PciDpc {
+            capability: self.read_u16(pos + 4)?,
+            control: self.read_u16(pos + 6)?,
+            status: self.read_u16(pos + 8)?,
+        })
+    }
+}
+
+impl From<Vec<u8>> for PciConfig {
+    fn from(data: Vec<u8>) -> Self {
+        Self::from_bytes(data)
+    }
+}
+
+/// The command register.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PciCommand(pub u16);
+
+impl PciCommand {
+    pub fn io_space(self) -> bool {
+        self.0 & 0x1 != 0
+    }
+
+    pub fn memory_space(self) -> bool {
+        self.0 & 0x2 != 0
+    }
+
+    pub fn bus_master(self) -> bool {
+        self.0 & 0x4 != 0
+    }
+
+    pub fn parity_error_response(self) -> bool {
+        self.0 & 0x40 != 0
+    }
+
+    pub fn serr(self) -> bool {
+        self.0 & 0x100 != 0
+    }
+
+    pub fn interrupt_disable(self) -> bool {
+        self.0 & 0x400 != 0
+    }
+}
+
+/// The status register.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PciStatus(pub u16);
+
+impl PciStatus {
+    pub fn interrupt(self) -> bool {
+        self.0 & 0x8 != 0
+    }
+
+    pub fn capabilities_list(self) -> bool {
+        self.0 & PCI_STATUS_CAP_LIST != 0
+    }
+
+    pub fn master_data_parity_error(self) -> bool {
+        self.0 & 0x100 != 0
+    }
+
+    pub fn signaled_target_abort(self) -> bool {
+        self.0 & 0x800 != 0
+    }
+
+    pub fn received_target_abort(self) -> bool {
+        self.0 & 0x1000 != 0
+    }
+
+    pub fn received_master_abort(self) -> bool {
+        self.0 & 0x2000 != 0
+    }
+
+    pub fn signaled_system_error(self) -> bool {
+        self.0 & 0x4000 != 0
+    }
+
+    pub fn detected_parity_error(self) -> bool {
+        self.0 & 0x8000 != 0
+    }
+}
+
+/// The layout of the configuration header.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PciHeaderType {
+    Normal,
+    Bridge,
+    CardBus,
+    Unknown(u8),
+}
+
+impl From<u8> for PciHeaderType {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => PciHeaderType::Normal,
+            1 => PciHeaderType::Bridge,
+            2 => PciHeaderType::CardBus,
+            value => PciHeaderType::Unknown(value),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PciBarKind {
+    Io,
+    Memory32,
+    Memory64,
+}
+
+/// A base address register, as programmed in configuration space.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PciBar {
+    pub index: u8,
+    pub address: u64,
+    pub kind: PciBarKind,
+    pub prefetchable: bool,
+}
+
+/// An entry of the classic capability list.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PciCapability {
+    pub id: u8,
+    pub offset: u8,
+    pub kind: PciCapabilityKind,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PciCapabilityKind {
+    PowerManagement(PciPowerManagement),
+    Msi(PciMsi),
+    MsiX(PciMsiX),
+    PciExpress(PciExpress),
+    VendorSpecific,
+    Other,
+}
+
+impl PciCapability {
+    pub fn name(&self) -> &'static str {
+        match self.id {
+            PCI_CAP_ID_PM => "Power Management",
+            0x02 => "AGP",
+            0x03 => "Vital Product Data",
+            0x04 => "Slot Identification",
+            PCI_CAP_ID_MSI => "MSI",
+            0x06 => "CompactPCI Hot Swap",
+            0x07 => "PCI-X",
+            0x08 => "HyperTransport",
+            PCI_CAP_ID_VNDR => "Vendor Specific",
+            0x0a => "Debug Port",
+            0x0b => "CompactPCI Central Resource Control",
+            0x0c => "PCI Standard Hot-Plug Controller",
+            0x0d => "Bridge Subsystem Vendor ID",
+            0x0e => "AGP 8x",
+            0x0f => "Secure Device",
+            PCI_CAP_ID_EXP => "PCI Express",
+            PCI_CAP_ID_MSIX => "MSI-X",
+            0x12 => "SATA Data/Index Configuration",
+            0x13 => "PCI Advanced Features",
+            0x14 => "Enhanced Allocation",
+            0x15 => "Flattening Portal Bridge",
+            _ => "Unknown",
+        }
+    }
+}
+
+/// An entry of the extended capability list.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PciExtCapability {
+    pub id: u16,
+    pub version: u8,
+    pub offset: u16,
+    pub kind: PciExtCapabilityKind,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PciExtCapabilityKind {
+    Aer(PciAer),
+    SerialNumber(u64),
+    VendorSpecific,
+    Acs(PciAcs),
+    Ari,
+    SrIov(PciSrIov),
+    ResizableBar,
+    LatencyTolerance,
+    Dpc(PciDpc),
+    L1Substates(PciL1Substates),
+    Other,
+}
+
+impl PciExtCapability {
+    pub fn name(&self) -> &'static str {
+        match self.id {
+            PCI_EXT_CAP_ID_ERR => "Advanced Error Reporting",
+            0x0002 => "Virtual Channel",
+            PCI_EXT_CAP_ID_DSN => "Device Serial Number",
+            0x0004 => "Power Budgeting",
+            0x0005 => "Root Complex Link Declaration",
+            0x0006 => "Root Complex Internal Link Control",
+            0x0007 => "Root Complex Event Collector Endpoint Association",
+            0x0008 => "Multi-Function Virtual Channel",
+            0x0009 => "Virtual Channel",
+            0x000a => "Root Complex Register Block",
+            PCI_EXT_CAP_ID_VNDR => "Vendor Specific",
+            0x000c => "Configuration Access Correlation",
+            PCI_EXT_CAP_ID_ACS => "Access Control Services",
+            PCI_EXT_CAP_ID_ARI => "Alternative Routing-ID Interpretation",
+            0x000f => "Address Translation Services",
+            PCI_EXT_CAP_ID_SRIOV => "Single Root I/O Virtualization",
+            0x0011 => "Multi Root I/O Virtualization",
+            0x0012 => "Multicast",
+            0x0013 => "Page Request Interface",
+            0x0014 => "AMD Reserved",
+            PCI_EXT_CAP_ID_REBAR => "Resizable BAR",
+            0x0016 => "Dynamic Power Allocation",
+            0x0017 => "TPH Requester",
+            PCI_EXT_CAP_ID_LTR => "Latency Tolerance Reporting",
+            0x0019 => "Secondary PCI Express",
+            0x001a => "Protocol Multiplexing",
+            0x001b => "Process Address Space ID",
+            0x001c => "LN Requester",
+            PCI_EXT_CAP_ID_DPC => "Downstream Port Containment",
+            PCI_EXT_CAP_ID_L1SS => "L1 PM Substates",
+            0x001f => "Precision Time Measurement",
+            0x0023 => "Designated Vendor-Specific",
+            0x0024 => "VF Resizable BAR",
+            0x0025 => "Data Link Feature",
+            0x0026 => "Physical Layer 16.0 GT/s",
+            0x0027 => "Lane Margining at the Receiver",
+            0x002a => "Physical Layer 32.0 GT/s",
+            _ => "Unknown",
+        }
+    }
+}
+
+/// The power management capability.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PciPowerManagement {
+    /// Power management capabilities register.
+    pub pmc: u16,
+    /// Power management control and status register.
+    pub pmcsr: u16,
+}
+
+impl PciPowerManagement {
+    pub fn version(&self) -> u8 {
+        (self.pmc & 0x7) as u8
+    }
+
+    pub fn d1_support(&self) -> bool {
+        self.pmc & 0x200 != 0
+    }
+
+    pub fn d2_support(&self) -> bool {
+        self.pmc & 0x400 != 0
+    }
+
+    /// Bitmask of the states from which PME# can be asserted, with D0 in bit 0 through
+    /// D3cold in bit 4.
+    pub fn pme_support(&self) -> u8 {
+        (self.pmc >> 11) as u8
+    }
+
+    /// The current power state, where 0 to 3 are D0 to D3hot.
+    pub fn power_state(&self) -> u8 {
+        (self.pmcsr & 0x3) as u8
+    }
+
+    pub fn no_soft_reset(&self) -> bool {
+        self.pmcsr & 0x8 != 0
+    }
+
+    pub fn pme_enable(&self) -> bool {
+        self.pmcsr & 0x100 != 0
+    }
+
+    pub fn pme_status(&self) -> bool {
+        self.pmcsr & 0x8000 != 0
+    }
+}
+
+/// The Message Signaled Interrupts capability.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PciMsi {
+    pub control: u16,
+    pub address: u64,
+    pub data: u16,
+}
+
+impl PciMsi {
+    pub fn enabled(&self) -> bool {
+        self.control & 0x1 != 0
+    }
+
+    /// Number of vectors the device is capable of.
+    pub fn vectors_capable(&self) -> u8 {
+        1 << ((self.control >> 1) & 0x7)
+    }
+
+    /// Number of vectors allocated by software.
+    pub fn vectors_enabled(&self) -> u8 {
+        1 << ((self.control >> 4) & 0x7)
+    }
+
+    pub fn is_64bit(&self) -> bool {
+        self.control & 0x80 != 0
+    }
+
+    pub fn per_vector_masking(&self) -> bool {
+        self.control & 0x100 != 0
+    }
+}
+
+/// The MSI-X capability.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PciMsiX {
+    pub control: u16,
+    pub table: u32,
+    pub pba: u32,
+}
+
+impl PciMsiX {
+    pub fn enabled(&self) -> bool {
+        self.control & 0x8000 != 0
+    }
+
+    pub fn function_mask(&self) -> bool {
+        self.control & 0x4000 != 0
+    }
+
+    pub fn table_size(&self) -> u16 {
+        (self.control & 0x7ff) + 1
+    }
+
+    /// BAR index and offset of the vector table.
+    pub fn table_location(&self) -> (u8, u32) {
+        ((self.table & 0x7) as u8, self.table & !0x7)
+    }
+
+    /// BAR index and offset of the pending bit array.
+    pub fn pba_location(&self) -> (u8, u32) {
+        ((self.pba & 0x7) as u8, self.pba & !0x7)
+    }
+}
+
+/// The role of a PCI Express function in the hierarchy.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PciExpressPortType {
+    Endpoint,
+    LegacyEndpoint,
+    RootPort,
+    UpstreamPort,
+    DownstreamPort,
+    PcieToPciBridge,
+    PciToPcieBridge,
+    RootComplexIntegratedEndpoint,
+    RootComplexEventCollector,
+    Unknown(u8),
+}
+
+impl From<u8> for PciExpressPortType {
+    fn from(value: u8) -> Self {
+        match value {
+            0x0 => PciExpressPortType::Endpoint,
+            0x1 => PciExpressPortType::LegacyEndpoint,
+            0x4 => PciExpressPortType::RootPort,
+            0x5 => PciExpressPortType::UpstreamPort,
+            0x6 => PciExpressPortType::DownstreamPort,
+            0x7 => PciExpressPortType::PcieToPciBridge,
+            0x8 => PciExpressPortType::PciToPcieBridge,
+            0x9 => PciExpressPortType::RootComplexIntegratedEndpoint,
+            0xa => PciExpressPortType::RootComplexEventCollector,
+            value => PciExpressPortType::Unknown(value),
+        }
+    }
+}
+
+/// The PCI Express capability, covering the device and link registers.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PciExpress {
+    pub capabilities: u16,
+    pub device_capabilities: u32,
+    pub device_control: u16,
+    pub device_status: u16,
+    pub link_capabilities: u32,
+    pub link_control: u16,
+    pub link_status: u16,
+}
+
+impl PciExpress {
+    pub fn version(&self) -> u8 {
+        (self.capabilities & 0xf) as u8
+    }
+
+    pub fn port_type(&self) -> PciExpressPortType {
+        PciExpressPortType::from(((self.capabilities >> 4) & 0xf) as u8)
+    }
+
+    pub fn slot_implemented(&self) -> bool {
+        self.capabilities & 0x100 != 0
+    }
+
+    /// Maximum payload size supported, in bytes.
+    pub fn max_payload_supported(&self) -> u16 {
+        128 << (self.device_capabilities & 0x7)
+    }
+
+    /// Maximum payload size configured, in bytes.
+    pub fn max_payload(&self) -> u16 {
+        128 << ((self.device_control >> 5) & 0x7)
+    }
+
+    /// Maximum read request size configured, in bytes.
+    pub fn max_read_request(&self) -> u16 {
+        128 << ((self.device_control >> 12) & 0x7)
+    }
+
+    /// Maximum link speed, encoded as in the supported link speeds vector: 1 for
+    /// 2.5 GT/s, 2 for 5 GT/s, 3 for 8 GT/s, and so on.
+    pub fn max_link_speed(&self) -> u8 {
+        (self.link_capabilities & 0xf) as u8
+    }
+
+    pub fn max_link_width(&self) -> u8 {
+        ((self.link_capabilities >> 4) & 0x3f) as u8
+    }
+
+    /// ASPM states supported by the link, with L0s in bit 0 and L1 in bit 1.
+    pub fn aspm_support(&self) -> u8 {
+        ((self.link_capabilities >> 10) & 0x3) as u8
+    }
+
+    pub fn port_number(&self) -> u8 {
+        (self.link_capabilities >> 24) as u8
+    }
+
+    /// ASPM states enabled on the link, with L0s in bit 0 and L1 in bit 1.
+    pub fn aspm_control(&self) -> u8 {
+        (self.link_control & 0x3) as u8
+    }
+
+    /// Negotiated link speed, encoded as for `max_link_speed`.
+    pub fn link_speed(&self) -> u8 {
+        (self.link_status & 0xf) as u8
+    }
+
+    pub fn link_width(&self) -> u8 {
+        ((self.link_status >> 4) & 0x3f) as u8
+    }
+}
+
+/// The Advanced Error Reporting capability.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PciAer {
+    pub uncorrectable_status: u32,
+    pub uncorrectable_mask: u32,
+    pub uncorrectable_severity: u32,
+    pub correctable_status: u32,
+    pub correctable_mask: u32,
+    pub capabilities_control: u32,
+}
+
+impl PciAer {
+    /// Bit position of the first uncorrectable error reported in the status register.
+    pub fn first_error_pointer(&self) -> u8 {
+        (self.capabilities_control & 0x1f) as u8
+    }
+}
+
+/// The Access Control Services capability.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PciAcs {
+    pub capability: u16,
+    pub control: u16,
+}
+
+impl PciAcs {
+    pub const SOURCE_VALIDATION: u16 = 0x01;
+    pub const TRANSLATION_BLOCKING: u16 = 0x02;
+    pub const P2P_REQUEST_REDIRECT: u16 = 0x04;
+    pub const P2P_COMPLETION_REDIRECT: u16 = 0x08;
+    pub const UPSTREAM_FORWARDING: u16 = 0x10;
+    pub const P2P_EGRESS_CONTROL: u16 = 0x20;
+    pub const DIRECT_TRANSLATED_P2P: u16 = 0x40;
+
+    pub fn supports(&self, flags: u16) -> bool {
+        self.capability & flags == flags
+    }
+
+    pub fn enabled(&self, flags: u16) -> bool {
+        self.control & flags == flags
+    }
+}
+
+/// The Single Root I/O Virtualization capability.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PciSrIov {
+    pub capabilities: u32,
+    pub control: u16,
+    pub status: u16,
+    pub initial_vfs: u16,
+    pub total_vfs: u16,
+    pub num_vfs: u16,
+    pub first_vf_offset: u16,
+    pub vf_stride: u16,
+    pub vf_device_id: u16,
+    pub supported_page_sizes: u32,
+    pub system_page_size: u32,
+}
+
+impl PciSrIov {
+    pub fn vf_enabled(&self) -> bool {
+        self.control & 0x1 != 0
+    }
+
+    pub fn vf_memory_space(&self) -> bool {
+        self.control & 0x8 != 0
+    }
+}
+
+/// The Downstream Port Containment capability.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PciDpc {
+    pub capability: u16,
+    pub control: u16,
+    pub status: u16,
+}
+
+impl PciDpc {
+    pub fn root_port_extensions(&self) -> bool {
+        self.capability & 0x20 != 0
+    }
+
+    pub fn software_trigger(&self) -> bool {
+        self.capability & 0x80 != 0
+    }
+
+    /// Conditions that trigger containment: 0 disabled, 1 fatal errors, 2 fatal and
+    /// non-fatal errors.
+    pub fn trigger_enable(&self) -> u8 {
+        (self.control & 0x3) as u8
+    }
+
+    pub fn triggered(&self) -> bool {
+        self.status & 0x1 != 0
+    }
+
+    pub fn trigger_reason(&self) -> u8 {
+        ((self.status >> 1) & 0x3) as u8
+    }
+}
+
+/// The L1 PM Substates capability.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PciL1Substates {
+    pub capability: u32,
+    pub control1: u32,
+}
+
+impl PciL1Substates {
+    pub fn pci_pm_l1_2(&self) -> bool {
+        self.capability & 0x1 != 0
+    }
+
+    pub fn pci_pm_l1_1(&self) -> bool {
+        self.capability & 0x2 != 0
+    }
+
+    pub fn aspm_l1_2(&self) -> bool {
+        self.capability & 0x4 != 0
+    }
+
+    pub fn aspm_l1_1(&self) -> bool {
+        self.capability & 0x8 != 0
+    }
+
+    /// Substates enabled, using the same bits as the capability register.
+    pub fn enabled(&self) -> u8 {
+        (self.control1 & 0xf) as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Configuration space of a virtio-modern block device (1af4:1042), read from
+    /// `/sys/bus/pci/devices/*/config` inside a virtual machine. It has a single 64-bit
+    /// memory BAR and vendor-specific capabilities, but no power management or PCIe
+    /// capability. Every byte after 0xb0 is zero.
+    const VIRTIO_BLK: &str = "
+        f4 1a 42 10 06 04 10 00 01 00 80 01 00 00 00 00
+        04 00 08 00 40 00 00 00 00 00 00 00 00 00 00 00
+        00 00 00 00 00 00 00 00 00 00 00 00 f4 1a 42 10
+        00 00 00 00 40 00 00 00 00 00 00 00 00 00 00 00
+        09 50 10 01 00 00 00 00 00 00 00 00 38 00 00 00
+        09 60 10 03 00 00 00 00 00 20 00 00 01 00 00 00
+        09 70 10 04 00 00 00 00 00 40 00 00 00 10 00 00
+        09 84 14 02 00 00 00 00 00 60 00 00 00 10 00 00
+        04 00 00 00 09 98 14 05 00 00 00 00 00 00 00 00
+        00 00 00 00 00 00 00 00 11 00 01 80 00 80 00 00
+        00 80 04 00 00 00 00 00 00 00 00 00 00 00 00 00
+    ";
+
+    fn capture(hex: &str, size: usize) -> PciConfig {
+        let mut data = hex
+            .split_whitespace()
+            .map(|byte| u8::from_str_radix(byte, 16).unwrap())
+            .collect::<Vec<_>>();
+        data.resize(size, 0);
+        PciConfig::from_bytes(data)
+    }
+
+    /// A blank configuration space of `size` bytes, with a normal header and the
+    /// capability list bit set.
+    fn blank(size: usize) -> PciConfig {
+        let mut config = PciConfig::from_bytes(vec![0; size]);
+        config.put16(PCI_STATUS, PCI_STATUS_CAP_LIST);
+        config
+    }
+
+    impl PciConfig {
+        fn put8(&mut self, offset: usize, value: u8) {
+            self.data[offset] = value;
+        }
+
+        fn put16(&mut self, offset: usize, value: u16) {
+            self.data[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+        }
+
+        fn put32(&mut self, offset: usize, value: u32) {
+            self.data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        }
+
+        /// Adds a classic capability, which the previous one, or the header, points to.
+        fn put_cap(&mut self, previous: usize, offset: usize, id: u8) {
+            let next = if previous == 0 {
+                PCI_CAPABILITY_LIST
+            } else {
+                previous + 1
+            };
+            self.put8(next, offset as u8);
+            self.put8(offset, id);
+        }
+
+        /// Adds an extended capability header which points to `next`.
+        fn put_ext_cap(&mut self, offset: usize, id: u16, version: u32, next: usize) {
+            self.put32(offset, (next as u32) << 20 | version << 16 | id as u32);
+        }
+    }
+
+    #[test]
+    fn virtio_header() {
+        let config = capture(VIRTIO_BLK, PCI_CFG_SPACE_SIZE);
+        assert_eq!(config.vendor_id(), Some(0x1af4));
+        assert_eq!(config.device_id(), Some(0x1042));
+        assert_eq!(config.revision(), Some(1));
+        assert_eq!(config.class(), Some(0x018000));
+        assert_eq!(config.header_type(), Some(PciHeaderType::Normal));
+        assert_eq!(config.is_multifunction(), Some(false));
+        assert_eq!(config.subsystem_vendor_id(), Some(0x1af4));
+        assert_eq!(config.subsystem_id(), Some(0x1042));
+        assert_eq!(config.bridge_buses(), None);
+
+        let command = config.command().unwrap();
+        assert!(command.memory_space());
+        assert!(command.bus_master());
+        assert!(command.interrupt_disable());
+        assert!(!command.io_space());
+        assert!(config.status().unwrap().capabilities_list());
+    }
+
+    #[test]
+    fn virtio_bars() {
+        let config = capture(VIRTIO_BLK, PCI_CFG_SPACE_SIZE);
+        assert_eq!(
+            config.bars(),
+            vec![PciBar {
+                index: 0,
+                address: 0x40_0008_0000,
+                kind: PciBarKind::Memory64,
+                prefetchable: false,
+            }]
+        );
+    }
+
+    #[test]
+    fn virtio_capabilities() {
+        let config = capture(VIRTIO_BLK, PCI_CFG_SPACE_SIZE);
+        let capabilities = config.capabilities();
+        let offsets = capabilities
+            .iter()
+            .map(|cap| (cap.id, cap.offset))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            offsets,
+            vec![
+                (PCI_CAP_ID_VNDR, 0x40),
+                (PCI_CAP_ID_VNDR, 0x50),
+                (PCI_CAP_ID_VNDR, 0x60),
+                (PCI_CAP_ID_VNDR, 0x70),
+                (PCI_CAP_ID_VNDR, 0x84),
+                (PCI_CAP_ID_MSIX, 0x98),
+            ]
+        );
+        assert_eq!(capabilities[0].kind, PciCapabilityKind::VendorSpecific);
+        assert_eq!(capabilities[5].name(), "MSI-X");
+
+        let msix = match config.find_capability(PCI_CAP_ID_MSIX).unwrap().kind {
+            PciCapabilityKind::MsiX(msix) => msix,
+            kind => panic!("unexpected {:?}", kind),
+        };
+        assert!(msix.enabled());
+        assert!(!msix.function_mask());
+        assert_eq!(msix.table_size(), 2);
+        assert_eq!(msix.table_location(), (0, 0x8000));
+        assert_eq!(msix.pba_location(), (0, 0x48000));
+
+        // Conventional configuration space has no extended capabilities.
+        assert!(config.extended_capabilities().is_empty());
+    }
+
+    #[test]
+    fn unprivileged_read() {
+        // Unprivileged readers only get the first 64 bytes.
+        let config = capture(VIRTIO_BLK, 64);
+        assert_eq!(config.vendor_id(), Some(0x1af4));
+        assert_eq!(config.bars().len(), 1);
+        assert!(config.capabilities().is_empty());
+        assert!(config.extended_capabilities().is_empty());
+    }
+
+    #[test]
+    fn out_of_range_read() {
+        let config = capture(VIRTIO_BLK, 64);
+        assert_eq!(config.read_u8(64), None);
+        assert_eq!(config.read_u16(63), None);
+        assert_eq!(config.read_u32(62), None);
+        assert_eq!(config.read_u64(60), None);
+        assert_eq!(config.read_u16(usize::MAX), None);
+        assert_eq!(config.read_u32(usize::MAX - 2), None);
+        assert_eq!(config.read_u64(usize::MAX - 4), None);
+    }
+
+    #[test]
+    fn capability_list_bit() {
+        let mut config = capture(VIRTIO_BLK, PCI_CFG_SPACE_SIZE);
+        config.put16(PCI_STATUS, 0);
+        assert!(config.capabilities().is_empty());
+    }
+
+    #[test]
+    fn capability_loop() {
+        let mut config = blank(PCI_CFG_SPACE_SIZE);
+        config.put_cap(0, 0x40, PCI_CAP_ID_VNDR);
+        config.put_cap(0x40, 0x50, PCI_CAP_ID_VNDR);
+        config.put8(0x51, 0x40);
+        let capabilities = config.capabilities();
+        assert_eq!(capabilities.len(), MAX_CAPABILITIES);
+        assert_eq!(capabilities[2].offset, 0x40);
+    }
+
+    #[test]
+    fn capability_truncated() {
+        // The pointer leads past the end of the data.
+        let mut config = blank(0x41);
+        config.put8(PCI_CAPABILITY_LIST, 0x40);
+        config.put8(0x40, PCI_CAP_ID_PM);
+        assert!(config.capabilities().is_empty());
+
+        // The header fits, but the 64-bit MSI registers do not.
+        let mut config = blank(PCI_CFG_SPACE_SIZE);
+        config.put_cap(0, 0xf8, PCI_CAP_ID_MSI);
+        config.put16(0xfa, 0x80);
+        let capabilities = config.capabilities();
+        assert_eq!(capabilities.len(), 1);
+        assert_eq!(capabilities[0].kind, PciCapabilityKind::Other);
+
+        // Pointers into the header end the list.
+        let mut config = blank(PCI_CFG_SPACE_SIZE);
+        config.put_cap(0, 0x40, PCI_CAP_ID_VNDR);
+        config.put8(0x41, 0x10);
+        assert_eq!(config.capabilities().len(), 1);
+    }
+
+    #[test]
+    fn bars_64bit_pairing() {
+        let mut config = blank(PCI_CFG_SPACE_SIZE);
+        // 32-bit memory, then a prefetchable 64-bit pair, then I/O.
+        config.put32(PCI_BASE_ADDRESS_0, 0xfe00_0000);
+        config.put32(PCI_BASE_ADDRESS_0 + 4, 0xc000_000c);
+        config.put32(PCI_BASE_ADDRESS_0 + 8, 0x0000_0001);
+        config.put32(PCI_BASE_ADDRESS_0 + 12, 0x0000_e001);
+        // A 64-bit BAR whose address is entirely in the upper half.
+        config.put32(PCI_BASE_ADDRESS_0 + 16, 0x0000_0004);
+        config.put32(PCI_BASE_ADDRESS_0 + 20, 0x0000_0020);
+
+        assert_eq!(
+            config.bars(),
+            vec![
+                PciBar {
+                    index: 0,
+                    address: 0xfe00_0000,
+                    kind: PciBarKind::Memory32,
+                    prefetchable: false,
+                },
+                PciBar {
+                    index: 1,
+                    address: 0x1_c000_0000,
+                    kind: PciBarKind::Memory64,
+                    prefetchable: true,
+                },
+                PciBar {
+                    index: 3,
+                    address: 0xe000,
+                    kind: PciBarKind::Io,
+                    prefetchable: false,
+                },
+                PciBar {
+                    index: 4,
+                    address: 0x20_0000_0000,
+                    kind: PciBarKind::Memory64,
+                    prefetchable: false,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn bridge_header() {
+        let mut config = blank(PCI_CFG_SPACE_SIZE);
+        config.put8(PCI_HEADER_TYPE, 0x81);
+        config.put32(PCI_BASE_ADDRESS_0, 0xfd00_0000);
+        // Bridges only have two BARs, so the bus numbers are not decoded as BARs.
+        config.put8(PCI_PRIMARY_BUS, 0x00);
+        config.put8(PCI_SECONDARY_BUS, 0x02);
+        config.put8(PCI_SUBORDINATE_BUS, 0x05);
+
+        assert_eq!(config.header_type(), Some(PciHeaderType::Bridge));
+        assert_eq!(config.is_multifunction(), Some(true));
+        assert_eq!(config.bridge_buses(), Some((0, 2, 5)));
+        assert_eq!(config.subsystem_id(), None);
+        assert_eq!(config.bars().len(), 1);
+    }
+
+    fn msi(config: &PciConfig) -> PciMsi {
+        match config.find_capability(PCI_CAP_ID_MSI).unwrap().kind {
+            PciCapabilityKind::Msi(msi) => msi,
+            kind => panic!("unexpected {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn msi_32bit() {
+        let mut config = blank(PCI_CFG_SPACE_SIZE);
+        config.put_cap(0, 0x50, PCI_CAP_ID_MSI);
+        // Enabled, 4 vectors capable and 2 enabled.
+        config.put16(0x52, 0x0015);
+        config.put32(0x54, 0xfee0_0000);
+        config.put16(0x58, 0x4021);
+        config.put16(0x5c, 0xffff);
+
+        let msi = msi(&config);
+        assert!(msi.enabled());
+        assert!(!msi.is_64bit());
+        assert!(!msi.per_vector_masking());
+        assert_eq!(msi.vectors_capable(), 4);
+        assert_eq!(msi.vectors_enabled(), 2);
+        assert_eq!(msi.address, 0xfee0_0000);
+        assert_eq!(msi.data, 0x4021);
+    }
+
+    #[test]
+    fn msi_64bit() {
+        let mut config = blank(PCI_CFG_SPACE_SIZE);
+        config.put_cap(0, 0x50, PCI_CAP_ID_MSI);
+        config.put16(0x52, 0x0181);
+        config.put32(0x54, 0xfee0_1000);
+        config.put32(0x58, 0x0000_0001);
+        config.put16(0x5c, 0x4022);
+
+        let msi = msi(&config);
+        assert!(msi.is_64bit());
+        assert!(msi.per_vector_masking());
+        assert_eq!(msi.vectors_capable(), 1);
+        assert_eq!(msi.address, 0x1_fee0_1000);
+        assert_eq!(msi.data, 0x4022);
+    }
+
+    #[test]
+    fn power_management_and_express() {
+        let mut config = blank(PCI_CFG_SPACE_SIZE);
+        config.put_cap(0, 0x40, PCI_CAP_ID_PM);
+        config.put16(0x42, 0xc803);
+        config.put16(0x44, 0x8108);
+        config.put_cap(0x40, 0x70, PCI_CAP_ID_EXP);
+        // Version 2 root port with a slot.
+        config.put16(0x72, 0x0142);
+        config.put32(0x74, 0x0000_8002);
+        config.put16(0x78, 0x2030);
+        config.put16(0x7a, 0x0000);
+        config.put32(0x7c, 0x0300_0c44);
+        config.put16(0x80, 0x0002);
+        config.put16(0x82, 0x1043);
+
+        let capabilities = config.capabilities();
+        assert_eq!(capabilities.len(), 2);
+
+        let pm = match capabilities[0].kind {
+            PciCapabilityKind::PowerManagement(pm) => pm,
+            ref kind => panic!("unexpected {:?}", kind),
+        };
+        assert_eq!(pm.version(), 3);
+        assert!(!pm.d1_support());
+        assert!(!pm.d2_support());
+        assert_eq!(pm.pme_support(), 0x19);
+        assert_eq!(pm.power_state(), 0);
+        assert!(pm.no_soft_reset());
+        assert!(pm.pme_enable());
+        assert!(pm.pme_status());
+
+        let express = match capabilities[1].kind {
+            PciCapabilityKind::PciExpress(express) => express,
+            ref kind => panic!("unexpected {:?}", kind),
+        };
+        assert_eq!(express.version(), 2);
+        assert_eq!(express.port_type(), PciExpressPortType::RootPort);
+        assert!(express.slot_implemented());
+        assert_eq!(express.max_payload_supported(), 512);
+        assert_eq!(express.max_payload(), 256);
+        assert_eq!(express.max_read_request(), 512);
+        assert_eq!(express.max_link_speed(), 4);
+        assert_eq!(express.max_link_width(), 4);
+        assert_eq!(express.aspm_support(), 3);
+        assert_eq!(express.port_number(), 3);
+        assert_eq!(express.aspm_control(), 2);
+        assert_eq!(express.link_speed(), 3);
+        assert_eq!(express.link_width(), 4);
+    }
+
+    /// Extended configuration space with every extended capability which is decoded.
+    fn extended() -> PciConfig {
+        let mut config = blank(PCI_CFG_SPACE_EXP_SIZE);
+
+        config.put_ext_cap(0x100, PCI_EXT_CAP_ID_ERR, 2, 0x148);
+        config.put32(0x104, 0x0000_4000);
+        config.put32(0x108, 0x0040_0000);
+        config.put32(0x10c, 0x0046_2030);
+        config.put32(0x110, 0x0000_0041);
+        config.put32(0x114, 0x0000_e000);
+        config.put32(0x118, 0x0000_00ae);
+
+        config.put_ext_cap(0x148, PCI_EXT_CAP_ID_DSN, 1, 0x158);
+        config.put32(0x14c, 0x89ab_cdef);
+        config.put32(0x150, 0x0123_4567);
+
+        config.put_ext_cap(0x158, PCI_EXT_CAP_ID_ACS, 1, 0x168);
+        config.put16(0x15c, 0x005f);
+        config.put16(0x15e, 0x001d);
+
+        config.put_ext_cap(0x168, PCI_EXT_CAP_ID_SRIOV, 1, 0x1a8);
+        config.put32(0x16c, 0x0000_0002);
+        config.put16(0x170, 0x0009);
+        config.put16(0x174, 64);
+        config.put16(0x176, 64);
+        config.put16(0x178, 8);
+        config.put16(0x17c, 0x80);
+        config.put16(0x17e, 2);
+        config.put16(0x182, 0x154c);
+        config.put32(0x184, 0x0000_0553);
+        config.put32(0x188, 0x0000_0001);
+
+        config.put_ext_cap(0x1a8, PCI_EXT_CAP_ID_DPC, 1, 0x1f0);
+        config.put16(0x1ac, 0x00a0);
+        config.put16(0x1ae, 0x0002);
+        config.put16(0x1b0, 0x0003);
+
+        config.put_ext_cap(0x1f0, PCI_EXT_CAP_ID_L1SS, 1, 0x200);
+        config.put32(0x1f4, 0x0028_281f);
+        config.put32(0x1f8, 0x4000_000a);
+
+        config.put_ext_cap(0x200, 0x0019, 1, 0);
+
+        config
+    }
+
+    #[test]
+    fn extended_capabilities() {
+        let config = extended();
+        let capabilities = config.extended_capabilities();
+        let ids = capabilities
+            .iter()
+            .map(|cap| (cap.id, cap.version, cap.offset))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            vec![
+                (PCI_EXT_CAP_ID_ERR, 2, 0x100),
+                (PCI_EXT_CAP_ID_DSN, 1, 0x148),
+                (PCI_EXT_CAP_ID_ACS, 1, 0x158),
+                (PCI_EXT_CAP_ID_SRIOV, 1, 0x168),
+                (PCI_EXT_CAP_ID_DPC, 1, 0x1a8),
+                (PCI_EXT_CAP_ID_L1SS, 1, 0x1f0),
+                (0x0019, 1, 0x200),
+            ]
+        );
+        assert_eq!(capabilities[6].kind, PciExtCapabilityKind::Other);
+        assert_eq!(capabilities[6].name(), "Secondary PCI Express");
+
+        match capabilities[0].kind {
+            PciExtCapabilityKind::Aer(aer) => {
+                assert_eq!(aer.uncorrectable_status, 0x4000);
+                assert_eq!(aer.uncorrectable_mask, 0x0040_0000);
+                assert_eq!(aer.uncorrectable_severity, 0x0046_2030);
+                assert_eq!(aer.correctable_status, 0x41);
+                assert_eq!(aer.correctable_mask, 0xe000);
+                assert_eq!(aer.first_error_pointer(), 0x0e);
+            }
+            ref kind => panic!("unexpected {:?}", kind),
+        }
+
+        assert_eq!(
+            capabilities[1].kind,
+            PciExtCapabilityKind::SerialNumber(0x0123_4567_89ab_cdef)
+        );
+
+        match capabilities[2].kind {
+            PciExtCapabilityKind::Acs(acs) => {
+                assert!(acs.supports(PciAcs::SOURCE_VALIDATION | PciAcs::P2P_REQUEST_REDIRECT));
+                assert!(!acs.supports(PciAcs::P2P_EGRESS_CONTROL));
+                assert!(acs.enabled(PciAcs::UPSTREAM_FORWARDING));
+                assert!(!acs.enabled(PciAcs::TRANSLATION_BLOCKING));
+            }
+            ref kind => panic!("unexpected {:?}", kind),
+        }
+
+        match capabilities[3].kind {
+            PciExtCapabilityKind::SrIov(sriov) => {
+                assert!(sriov.vf_enabled());
+                assert!(sriov.vf_memory_space());
+                assert_eq!(sriov.total_vfs, 64);
+                assert_eq!(sriov.num_vfs, 8);
+                assert_eq!(sriov.first_vf_offset, 0x80);
+                assert_eq!(sriov.vf_stride, 2);
+                assert_eq!(sriov.vf_device_id, 0x154c);
+                assert_eq!(sriov.supported_page_sizes, 0x553);
+                assert_eq!(sriov.system_page_size, 1);
+            }
+            ref kind => panic!("unexpected {:?}", kind),
+        }
+
+        match capabilities[4].kind {
+            PciExtCapabilityKind::Dpc(dpc) => {
+                assert!(dpc.root_port_extensions());
+                assert!(dpc.software_trigger());
+                assert_eq!(dpc.trigger_enable(), 2);
+                assert!(dpc.triggered());
+                assert_eq!(dpc.trigger_reason(), 1);
+            }
+            ref kind => panic!("unexpected {:?}", kind),
+        }
+
+        match capabilities[5].kind {
+            PciExtCapabilityKind::L1Substates(l1ss) => {
+                assert!(l1ss.pci_pm_l1_2());
+                assert!(l1ss.pci_pm_l1_1());
+                assert!(l1ss.aspm_l1_2());
+                assert!(l1ss.aspm_l1_1());
+                assert_eq!(l1ss.enabled(), 0xa);
+            }
+            ref kind => panic!("unexpected {:?}", kind),
+        }
+
+        assert_eq!(
+            config
+                .find_extended_capability(PCI_EXT_CAP_ID_DPC)
+                .map(|cap| cap.offset),
+            Some(0x1a8)
+        );
+        assert_eq!(config.find_extended_capability(PCI_EXT_CAP_ID_REBAR), None);
+    }
+
+    #[test]
+    fn extended_capability_loop() {
+        let mut config = blank(PCI_CFG_SPACE_EXP_SIZE);
+        config.put_ext_cap(0x100, PCI_EXT_CAP_ID_ARI, 1, 0x110);
+        config.put_ext_cap(0x110, PCI_EXT_CAP_ID_LTR, 1, 0x100);
+        let capabilities = config.extended_capabilities();
+        assert_eq!(capabilities.len(), MAX_EXT_CAPABILITIES);
+        assert_eq!(capabilities[2].kind, PciExtCapabilityKind::Ari);
+    }
+
+    #[test]
+    fn extended_capability_truncated() {
+        // The AER header fits, but its registers do not.
+        let mut config = PciConfig::from_bytes(vec![0; 0x108]);
+        config.put_ext_cap(0x100, PCI_EXT_CAP_ID_ERR, 2, 0x200);
+        let capabilities = config.extended_capabilities();
+        assert_eq!(capabilities.len(), 1);
+        assert_eq!(capabilities[0].kind, PciExtCapabilityKind::Other);
+
+        // A pointer back into conventional space ends the list.
+        let mut config = blank(PCI_CFG_SPACE_EXP_SIZE);
+        config.put_ext_cap(0x100, PCI_EXT_CAP_ID_ARI, 1, 0x40);
+        assert_eq!(config.extended_capabilities().len(), 1);
+
+        // Devices without extended capabilities read all zeroes or all ones.
+        let mut config = blank(PCI_CFG_SPACE_EXP_SIZE);
+        config.put32(0x100, 0xffff_ffff);
+        assert!(config.extended_capabilities().is_empty());
+        assert!(blank(PCI_CFG_SPACE_EXP_SIZE)
+            .extended_capabilities()
+            .is_empty());
+    }
+}
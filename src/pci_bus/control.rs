@@ -0,0 +1,141 @@
+use super::PciDevice;
+use crate::SysClass;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::str::FromStr;
+
+/// The power state of a PCI device, from `power_state`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PciPowerState {
+    D0,
+    D1,
+    D2,
+    D3Hot,
+    D3Cold,
+    Unknown,
+    Error,
+}
+
+impl FromStr for PciPowerState {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "D0" => PciPowerState::D0,
+            "D1" => PciPowerState::D1,
+            "D2" => PciPowerState::D2,
+            "D3hot" => PciPowerState::D3Hot,
+            "D3cold" => PciPowerState::D3Cold,
+            "unknown" => PciPowerState::Unknown,
+            "error" => PciPowerState::Error,
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("unknown power state: {}", s),
+                ))
+            }
+        })
+    }
+}
+
+impl PciDevice {
+    /// Rescans all PCI buses, bringing back devices which were removed.
+    pub fn rescan_all() -> Result<()> {
+        let path = PciDevice::dir()
+            .parent()
+            .map(|bus| bus.join("rescan"))
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, "rescan"))?;
+        fs::write(path, "1")
+    }
+
+    /// Rescans the bus of this device, and the buses behind it if it is a bridge.
+    pub fn rescan(&self) -> Result<()> {
+        self.write_file("rescan", "1")
+    }
+
+    /// Removes the device, rescans all buses, and returns the device as found again.
+    ///
+    /// # Safety
+    ///
+    /// The device is unusable by its driver, and anything depending on it, while it is
+    /// removed.
+    pub unsafe fn remove_and_rescan(&self) -> Result<PciDevice> {
+        self.remove()?;
+        PciDevice::rescan_all()?;
+        PciDevice::new(self.id())
+    }
+
+    /// Resets the device using the methods given by `reset_method`.
+    ///
+    /// # Safety
+    ///
+    /// The state of the device is lost, which a bound driver may not expect.
+    pub unsafe fn reset(&self) -> Result<()> {
+        self.write_file("reset", "1")
+    }
+
+    /// Reset methods which are enabled for the device, in the order they are tried.
+    pub fn reset_method(&self) -> Result<Vec<String>> {
+        Ok(self
+            .read_file("reset_method")?
+            .split_whitespace()
+            .map(str::to_owned)
+            .collect())
+    }
+
+    /// Selects the reset methods to try, such as `flr` or `bus`, in order. An empty list
+    /// disables reset, and `default` restores all supported methods.
+    pub fn set_reset_method(&self, methods: &[&str]) -> Result<()> {
+        // An empty write never reaches the kernel, so a lone newline clears the list.
+        if methods.is_empty() {
+            return self.write_file("reset_method", "\n");
+        }
+
+        self.write_file("reset_method", methods.join(" "))
+    }
+
+    method!(enable parse_file u8);
+
+    /// Enables or disables the device.
+    ///
+    /// # Safety
+    ///
+    /// A disabled device stops responding to memory and I/O accesses, and to DMA.
+    pub unsafe fn set_enable(&self, enable: bool) -> Result<()> {
+        self.write_file("enable", if enable { "1" } else { "0" })
+    }
+
+    method!(power_state parse_file PciPowerState);
+
+    method!(d3cold_allowed parse_file u8);
+
+    set_method!("d3cold_allowed", set_d3cold_allowed u8);
+
+    method!(broken_parity_status parse_file u8);
+
+    set_method!("broken_parity_status", set_broken_parity_status u8);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use std::fs;
+
+    #[test]
+    fn set_reset_method() {
+        let dir = TempDir::new("reset-method");
+        let device = unsafe { PciDevice::from_path_unchecked(dir.path().to_owned()) };
+
+        fs::write(dir.path().join("reset_method"), "").unwrap();
+        device.set_reset_method(&["flr", "bus"]).unwrap();
+        assert_eq!(device.reset_method().unwrap(), vec!["flr", "bus"]);
+
+        fs::write(dir.path().join("reset_method"), "").unwrap();
+        device.set_reset_method(&[]).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("reset_method")).unwrap(),
+            "\n"
+        );
+        assert!(device.reset_method().unwrap().is_empty());
+    }
+}
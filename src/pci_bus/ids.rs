@@ -0,0 +1,372 @@
+use super::PciDevice;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Error, ErrorKind};
+use std::path::Path;
+
+/// Locations where distributions install the PCI ID database.
+const SYSTEM_PATHS: &[&str] = &[
+    "/usr/share/hwdata/pci.ids",
+    "/usr/share/misc/pci.ids",
+    "/usr/share/pci.ids",
+    "/usr/local/share/pci.ids",
+];
+
+/// A small subset of the database, with all device classes but only common vendors and a
+/// handful of their devices. Most device and subsystem names are missing from it.
+const EMBEDDED: &str = include_str!("pci.ids");
+
+#[derive(Clone, Debug, Default)]
+struct Vendor {
+    name: String,
+    devices: BTreeMap<u16, Device>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Device {
+    name: String,
+    subsystems: BTreeMap<(u16, u16), String>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Class {
+    name: String,
+    subclasses: BTreeMap<u8, Subclass>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Subclass {
+    name: String,
+    prog_ifs: BTreeMap<u8, String>,
+}
+
+/// Names of vendors, devices and classes from a `pci.ids` database.
+#[derive(Clone, Debug, Default)]
+pub struct PciIds {
+    vendors: BTreeMap<u16, Vendor>,
+    classes: BTreeMap<u8, Class>,
+}
+
+impl PciIds {
+    /// Loads the system database, falling back to the embedded subset if none is installed.
+    pub fn load() -> Self {
+        Self::system().unwrap_or_else(|_| Self::embedded())
+    }
+
+    /// Loads the database installed by the system.
+    pub fn system() -> io::Result<Self> {
+        for path in SYSTEM_PATHS {
+            match Self::from_path(path) {
+                Ok(ids) => return Ok(ids),
+                Err(why) if why.kind() == ErrorKind::NotFound => continue,
+                Err(why) => return Err(why),
+            }
+        }
+
+        Err(Error::new(
+            ErrorKind::NotFound,
+            "pci.ids: not found in any system location",
+        ))
+    }
+
+    /// Loads the subset of the database which is embedded in this crate.
+    pub fn embedded() -> Self {
+        Self::parse(EMBEDDED)
+    }
+
+    pub fn from_path<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        // Older databases contain names which are not valid UTF-8.
+        let data = fs::read(path)?;
+        Ok(Self::parse(&String::from_utf8_lossy(&data)))
+    }
+
+    /// Parses the contents of a `pci.ids` file. Malformed lines are skipped.
+    pub fn parse(data: &str) -> Self {
+        let mut ids = PciIds::default();
+        let mut vendor = None;
+        let mut device = None;
+        let mut class = None;
+        let mut subclass = None;
+
+        for line in data.lines() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let depth = line.bytes().take_while(|&b| b == b'\t').count();
+            let line = &line[depth..];
+
+            if depth == 0 {
+                vendor = None;
+                device = None;
+                class = None;
+                subclass = None;
+
+                if let Some(line) = line.strip_prefix("C ") {
+                    if let Some((id, name)) = split_id(line, u8::from_str_radix) {
+                        ids.classes.insert(
+                            id,
+                            Class {
+                                name,
+                                ..Class::default()
+                            },
+                        );
+                        class = Some(id);
+                    }
+                } else if let Some((id, name)) = split_id(line, u16::from_str_radix) {
+                    ids.vendors.insert(
+                        id,
+                        Vendor {
+                            name,
+                            ..Vendor::default()
+                        },
+                    );
+                    vendor = Some(id);
+                }
+            } else if let Some(vendor) = vendor.and_then(|id| ids.vendors.get_mut(&id)) {
+                if depth == 1 {
+                    device = split_id(line, u16::from_str_radix).map(|(id, name)| {
+                        vendor.devices.insert(
+                            id,
+                            Device {
+                                name,
+                                ..Device::default()
+                            },
+                        );
+                        id
+                    });
+                } else if let Some(device) = device.and_then(|id| vendor.devices.get_mut(&id)) {
+                    let mut parts = line.splitn(3, char::is_whitespace);
+                    let subvendor = parts.next().and_then(|id| u16::from_str_radix(id, 16).ok());
+                    let subdevice = parts.next().and_then(|id| u16::from_str_radix(id, 16).ok());
+                    let name = parts.next().map(str::trim);
+                    if let (Some(subvendor), Some(subdevice), Some(name)) =
+                        (subvendor, subdevice, name)
+                    {
+                        device
+                            .subsystems
+                            .insert((subvendor, subdevice), name.to_owned());
+                    }
+                }
+            } else if let Some(class) = class.and_then(|id| ids.classes.get_mut(&id)) {
+                if depth == 1 {
+                    subclass = split_id(line, u8::from_str_radix).map(|(id, name)| {
+                        class.subclasses.insert(
+                            id,
+                            Subclass {
+                                name,
+                                ..Subclass::default()
+                            },
+                        );
+                        id
+                    });
+                } else if let Some(subclass) = subclass.and_then(|id| class.subclasses.get_mut(&id))
+                {
+                    if let Some((id, name)) = split_id(line, u8::from_str_radix) {
+                        subclass.prog_ifs.insert(id, name);
+                    }
+                }
+            }
+        }
+
+        ids
+    }
+
+    pub fn vendor_name(&self, vendor: u16) -> Option<&str> {
+        self.vendors.get(&vendor).map(|v| v.name.as_str())
+    }
+
+    pub fn device_name(&self, vendor: u16, device: u16) -> Option<&str> {
+        self.vendors
+            .get(&vendor)?
+            .devices
+            .get(&device)
+            .map(|d| d.name.as_str())
+    }
+
+    pub fn subsystem_name(
+        &self,
+        vendor: u16,
+        device: u16,
+        subvendor: u16,
+        subdevice: u16,
+    ) -> Option<&str> {
+        self.vendors
+            .get(&vendor)?
+            .devices
+            .get(&device)?
+            .subsystems
+            .get(&(subvendor, subdevice))
+            .map(String::as_str)
+    }
+
+    /// Name of the base class, from the top byte of a 24-bit class code.
+    pub fn class_name(&self, class: u32) -> Option<&str> {
+        self.classes
+            .get(&((class >> 16) as u8))
+            .map(|c| c.name.as_str())
+    }
+
+    pub fn subclass_name(&self, class: u32) -> Option<&str> {
+        self.classes
+            .get(&((class >> 16) as u8))?
+            .subclasses
+            .get(&((class >> 8) as u8))
+            .map(|s| s.name.as_str())
+    }
+
+    pub fn prog_if_name(&self, class: u32) -> Option<&str> {
+        self.classes
+            .get(&((class >> 16) as u8))?
+            .subclasses
+            .get(&((class >> 8) as u8))?
+            .prog_ifs
+            .get(&(class as u8))
+            .map(String::as_str)
+    }
+}
+
+/// Human-readable names of a PCI device. Names missing from the database are `None`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PciDeviceNames {
+    pub vendor: Option<String>,
+    pub device: Option<String>,
+    pub subsystem_vendor: Option<String>,
+    pub subsystem: Option<String>,
+    pub class: Option<String>,
+    pub subclass: Option<String>,
+    pub prog_if: Option<String>,
+}
+
+impl PciDevice {
+    /// Resolves the names of the device in the given database.
+    pub fn names(&self, ids: &PciIds) -> io::Result<PciDeviceNames> {
+        let vendor = self.vendor()?;
+        let device = self.device()?;
+        let class = self.class()?;
+        let subsystem_vendor = self.subsystem_vendor().ok();
+        let subsystem_device = self.subsystem_device().ok();
+
+        let owned = |name: Option<&str>| name.map(str::to_owned);
+
+        Ok(PciDeviceNames {
+            vendor: owned(ids.vendor_name(vendor)),
+            device: owned(ids.device_name(vendor, device)),
+            subsystem_vendor: owned(subsystem_vendor.and_then(|id| ids.vendor_name(id))),
+            subsystem: owned(
+                subsystem_vendor
+                    .zip(subsystem_device)
+                    .and_then(|(sv, sd)| ids.subsystem_name(vendor, device, sv, sd)),
+            ),
+            class: owned(ids.class_name(class)),
+            subclass: owned(ids.subclass_name(class)),
+            prog_if: owned(ids.prog_if_name(class)),
+        })
+    }
+}
+
+/// Splits a line into its hexadecimal identifier and the name which follows it.
+fn split_id<T>(
+    line: &str,
+    parse: fn(&str, u32) -> Result<T, std::num::ParseIntError>,
+) -> Option<(T, String)> {
+    let (id, name) = line.split_once(char::is_whitespace)?;
+    let id = parse(id, 16).ok()?;
+    Some((id, name.trim().to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use crate::SysClass;
+
+    const SNIPPET: &str = "\
+# A comment
+8086  Intel Corporation
+\t15f3  Ethernet Controller I225-V
+\t\t8086 0003  Ethernet Controller I225-V
+\t\tzzzz 0004  Malformed subsystem
+\txyz  Malformed device
+1af4  Red Hat, Inc.
+\t1042  Virtio 1.0 block device
+
+C 01  Mass storage controller
+\t08  Non-Volatile memory controller
+\t\t02  NVM Express
+C 02  Network controller
+\t00  Ethernet controller
+";
+
+    #[test]
+    fn parse() {
+        let ids = PciIds::parse(SNIPPET);
+        assert_eq!(ids.vendor_name(0x8086), Some("Intel Corporation"));
+        assert_eq!(
+            ids.device_name(0x8086, 0x15f3),
+            Some("Ethernet Controller I225-V")
+        );
+        assert_eq!(
+            ids.subsystem_name(0x8086, 0x15f3, 0x8086, 0x0003),
+            Some("Ethernet Controller I225-V")
+        );
+        assert_eq!(ids.subsystem_name(0x8086, 0x15f3, 0x8086, 0x0004), None);
+        assert_eq!(
+            ids.device_name(0x1af4, 0x1042),
+            Some("Virtio 1.0 block device")
+        );
+        assert_eq!(ids.device_name(0x1af4, 0x15f3), None);
+        assert_eq!(ids.vendor_name(0x10de), None);
+
+        assert_eq!(ids.class_name(0x010802), Some("Mass storage controller"));
+        assert_eq!(
+            ids.subclass_name(0x010802),
+            Some("Non-Volatile memory controller")
+        );
+        assert_eq!(ids.prog_if_name(0x010802), Some("NVM Express"));
+        assert_eq!(ids.prog_if_name(0x010801), None);
+        assert_eq!(ids.subclass_name(0x020000), Some("Ethernet controller"));
+        // Classes do not leak into the vendor which precedes them.
+        assert_eq!(ids.vendor_name(0x0001), None);
+    }
+
+    #[test]
+    fn embedded() {
+        let ids = PciIds::embedded();
+        assert_eq!(ids.vendor_name(0x8086), Some("Intel Corporation"));
+        assert_eq!(
+            ids.device_name(0x8086, 0x15f3),
+            Some("Ethernet Controller I225-V")
+        );
+        assert_eq!(ids.class_name(0x020000), Some("Network controller"));
+    }
+
+    #[test]
+    fn device_names() {
+        let dir = TempDir::new("pci-ids-names");
+        for (file, value) in &[
+            ("vendor", "0x8086"),
+            ("device", "0x15f3"),
+            ("subsystem_vendor", "0x8086"),
+            ("subsystem_device", "0x0003"),
+            ("class", "0x020000"),
+        ] {
+            fs::write(dir.path().join(file), format!("{}\n", value)).unwrap();
+        }
+
+        let device = unsafe { PciDevice::from_path_unchecked(dir.path().to_path_buf()) };
+        let names = device.names(&PciIds::parse(SNIPPET)).unwrap();
+        assert_eq!(
+            names,
+            PciDeviceNames {
+                vendor: Some("Intel Corporation".into()),
+                device: Some("Ethernet Controller I225-V".into()),
+                subsystem_vendor: Some("Intel Corporation".into()),
+                subsystem: Some("Ethernet Controller I225-V".into()),
+                class: Some("Network controller".into()),
+                subclass: Some("Ethernet controller".into()),
+                prog_if: None,
+            }
+        );
+    }
+}
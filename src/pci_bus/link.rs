@@ -0,0 +1,287 @@
+use super::PciDevice;
+use crate::SysClass;
+use std::fmt;
+use std::io::{Error, ErrorKind, Result};
+use std::str::FromStr;
+
+/// Transfer rate of a PCI Express link, per lane.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub enum PciLinkSpeed {
+    Gt2_5,
+    Gt5,
+    Gt8,
+    Gt16,
+    Gt32,
+    Gt64,
+    Unknown,
+}
+
+impl PciLinkSpeed {
+    /// Converts the encoding used by the link capability and status registers.
+    pub fn from_encoding(encoding: u8) -> Self {
+        match encoding {
+            1 => PciLinkSpeed::Gt2_5,
+            2 => PciLinkSpeed::Gt5,
+            3 => PciLinkSpeed::Gt8,
+            4 => PciLinkSpeed::Gt16,
+            5 => PciLinkSpeed::Gt32,
+            6 => PciLinkSpeed::Gt64,
+            _ => PciLinkSpeed::Unknown,
+        }
+    }
+
+    /// Transfer rate in gigatransfers per second.
+    pub fn gt_per_sec(self) -> Option<f32> {
+        match self {
+            PciLinkSpeed::Gt2_5 => Some(2.5),
+            PciLinkSpeed::Gt5 => Some(5.0),
+            PciLinkSpeed::Gt8 => Some(8.0),
+            PciLinkSpeed::Gt16 => Some(16.0),
+            PciLinkSpeed::Gt32 => Some(32.0),
+            PciLinkSpeed::Gt64 => Some(64.0),
+            PciLinkSpeed::Unknown => None,
+        }
+    }
+
+    /// The PCI Express generation which introduced this rate.
+    pub fn generation(self) -> Option<u8> {
+        match self {
+            PciLinkSpeed::Gt2_5 => Some(1),
+            PciLinkSpeed::Gt5 => Some(2),
+            PciLinkSpeed::Gt8 => Some(3),
+            PciLinkSpeed::Gt16 => Some(4),
+            PciLinkSpeed::Gt32 => Some(5),
+            PciLinkSpeed::Gt64 => Some(6),
+            PciLinkSpeed::Unknown => None,
+        }
+    }
+}
+
+impl FromStr for PciLinkSpeed {
+    type Err = Error;
+
+    /// Parses values such as `8.0 GT/s PCIe`, or `8 GT/s` on older kernels.
+    fn from_str(s: &str) -> Result<Self> {
+        let rate = s.split_whitespace().next().unwrap_or_default();
+        Ok(match rate.strip_suffix(".0").unwrap_or(rate) {
+            "2.5" => PciLinkSpeed::Gt2_5,
+            "5" => PciLinkSpeed::Gt5,
+            "8" => PciLinkSpeed::Gt8,
+            "16" => PciLinkSpeed::Gt16,
+            "32" => PciLinkSpeed::Gt32,
+            "64" => PciLinkSpeed::Gt64,
+            "Unknown" => PciLinkSpeed::Unknown,
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("unknown link speed: {}", s),
+                ))
+            }
+        })
+    }
+}
+
+impl fmt::Display for PciLinkSpeed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.gt_per_sec() {
+            Some(rate) => write!(f, "{:.1} GT/s", rate),
+            None => f.write_str("Unknown"),
+        }
+    }
+}
+
+/// Negotiated and maximum speed and width of a PCI Express link.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PciLinkStatus {
+    pub current_speed: PciLinkSpeed,
+    pub current_width: u8,
+    pub max_speed: PciLinkSpeed,
+    pub max_width: u8,
+}
+
+impl PciLinkStatus {
+    /// The link trained at a lower speed or with fewer lanes than the device supports.
+    pub fn is_degraded(&self) -> bool {
+        let known = |speed| speed != PciLinkSpeed::Unknown;
+        (known(self.current_speed) && known(self.max_speed) && self.current_speed < self.max_speed)
+            || (self.current_width != 0 && self.current_width < self.max_width)
+    }
+}
+
+/// Error counters reported by Advanced Error Reporting, by error type.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PciAerCounters {
+    counters: Vec<(String, u64)>,
+    total: u64,
+}
+
+impl PciAerCounters {
+    /// The counter of an error type, such as `RxErr` or `BadTLP`.
+    pub fn get(&self, name: &str) -> Option<u64> {
+        self.counters
+            .iter()
+            .find(|(counter, _)| counter == name)
+            .map(|&(_, count)| count)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u64)> {
+        self.counters
+            .iter()
+            .map(|(name, count)| (name.as_str(), *count))
+    }
+
+    /// Total number of errors, of all types.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+}
+
+impl FromStr for PciAerCounters {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        let mut counters = PciAerCounters::default();
+        for line in s.lines() {
+            let (name, count) = match line.split_once(char::is_whitespace) {
+                Some(pair) => pair,
+                None => continue,
+            };
+
+            let count = count
+                .trim()
+                .parse::<u64>()
+                .map_err(|err| Error::new(ErrorKind::InvalidData, format!("{}: {}", name, err)))?;
+
+            if name.starts_with("TOTAL_ERR_") {
+                counters.total = count;
+            } else {
+                counters.counters.push((name.to_owned(), count));
+            }
+        }
+
+        Ok(counters)
+    }
+}
+
+impl PciDevice {
+    method!(current_link_speed parse_file PciLinkSpeed);
+
+    method!(current_link_width parse_file u8);
+
+    method!(max_link_speed parse_file PciLinkSpeed);
+
+    method!(max_link_width parse_file u8);
+
+    pub fn link_status(&self) -> Result<PciLinkStatus> {
+        Ok(PciLinkStatus {
+            current_speed: self.current_link_speed()?,
+            current_width: self.current_link_width()?,
+            max_speed: self.max_link_speed()?,
+            max_width: self.max_link_width()?,
+        })
+    }
+
+    method!(aer_dev_correctable parse_file PciAerCounters);
+
+    method!(aer_dev_fatal parse_file PciAerCounters);
+
+    method!(aer_dev_nonfatal parse_file PciAerCounters);
+
+    method!(aer_rootport_total_err_cor parse_file u64);
+
+    method!(aer_rootport_total_err_fatal parse_file u64);
+
+    method!(aer_rootport_total_err_nonfatal parse_file u64);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use std::fs;
+
+    #[test]
+    fn link_speed() {
+        let speeds = [
+            ("2.5 GT/s PCIe", PciLinkSpeed::Gt2_5),
+            ("5.0 GT/s PCIe", PciLinkSpeed::Gt5),
+            ("8.0 GT/s PCIe", PciLinkSpeed::Gt8),
+            ("8 GT/s", PciLinkSpeed::Gt8),
+            ("16.0 GT/s PCIe", PciLinkSpeed::Gt16),
+            ("32.0 GT/s PCIe", PciLinkSpeed::Gt32),
+            ("64.0 GT/s PCIe", PciLinkSpeed::Gt64),
+            ("Unknown", PciLinkSpeed::Unknown),
+        ];
+        for (s, speed) in &speeds {
+            assert_eq!(s.parse::<PciLinkSpeed>().unwrap(), *speed);
+        }
+
+        for s in &["", "4.0 GT/s", "fast"] {
+            let err = s.parse::<PciLinkSpeed>().unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidData);
+        }
+
+        assert_eq!(PciLinkSpeed::Gt8.to_string(), "8.0 GT/s");
+        assert_eq!(PciLinkSpeed::Gt2_5.to_string(), "2.5 GT/s");
+        assert_eq!(PciLinkSpeed::Unknown.to_string(), "Unknown");
+        assert_eq!(PciLinkSpeed::from_encoding(4), PciLinkSpeed::Gt16);
+        assert_eq!(PciLinkSpeed::from_encoding(0), PciLinkSpeed::Unknown);
+        assert_eq!(PciLinkSpeed::Gt16.generation(), Some(4));
+    }
+
+    #[test]
+    fn degraded() {
+        let status = |current_speed, current_width, max_speed, max_width| PciLinkStatus {
+            current_speed,
+            current_width,
+            max_speed,
+            max_width,
+        };
+
+        assert!(!status(PciLinkSpeed::Gt16, 4, PciLinkSpeed::Gt16, 4).is_degraded());
+        assert!(status(PciLinkSpeed::Gt8, 4, PciLinkSpeed::Gt16, 4).is_degraded());
+        assert!(status(PciLinkSpeed::Gt16, 2, PciLinkSpeed::Gt16, 4).is_degraded());
+        // A link which is down, or whose speed is unknown, is not reported as degraded.
+        assert!(!status(PciLinkSpeed::Unknown, 0, PciLinkSpeed::Gt16, 4).is_degraded());
+        assert!(!status(PciLinkSpeed::Gt8, 4, PciLinkSpeed::Unknown, 4).is_degraded());
+    }
+
+    #[test]
+    fn aer_counters() {
+        let counters: PciAerCounters = "RxErr 2\nBadTLP 0\nBadDLLP 1\nTOTAL_ERR_COR 3\n"
+            .parse()
+            .unwrap();
+        assert_eq!(counters.get("RxErr"), Some(2));
+        assert_eq!(counters.get("BadDLLP"), Some(1));
+        assert_eq!(counters.get("Timeout"), None);
+        assert_eq!(counters.get("TOTAL_ERR_COR"), None);
+        assert_eq!(counters.total(), 3);
+        assert_eq!(
+            counters.iter().collect::<Vec<_>>(),
+            [("RxErr", 2), ("BadTLP", 0), ("BadDLLP", 1)]
+        );
+
+        let err = "RxErr many\n".parse::<PciAerCounters>().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn link_status() {
+        let dir = TempDir::new("pci-link-status");
+        fs::write(dir.path().join("current_link_speed"), "8.0 GT/s PCIe\n").unwrap();
+        fs::write(dir.path().join("current_link_width"), "4\n").unwrap();
+        fs::write(dir.path().join("max_link_speed"), "16.0 GT/s PCIe\n").unwrap();
+        fs::write(dir.path().join("max_link_width"), "4\n").unwrap();
+        fs::write(
+            dir.path().join("aer_dev_fatal"),
+            "Undefined 0\nDLP 1\nTOTAL_ERR_FATAL 1\n",
+        )
+        .unwrap();
+
+        let device = unsafe { PciDevice::from_path_unchecked(dir.path().to_path_buf()) };
+        let status = device.link_status().unwrap();
+        assert_eq!(status.current_speed, PciLinkSpeed::Gt8);
+        assert_eq!(status.max_speed, PciLinkSpeed::Gt16);
+        assert!(status.is_degraded());
+        assert_eq!(device.aer_dev_fatal().unwrap().get("DLP"), Some(1));
+    }
+}
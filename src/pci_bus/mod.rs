@@ -1,9 +1,47 @@
-use crate::{RuntimePM, RuntimePowerManagement, SysClass};
+use crate::{RuntimePM, RuntimePMExt, RuntimePowerManagement, SysClass};
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
 
-#[derive(Clone)]
+pub use self::config::{
+    PciAcs, PciAer, PciBar, PciBarKind, PciCapability, PciCapabilityKind, PciCommand, PciConfig,
+    PciDpc, PciExpress, PciExpressPortType, PciExtCapability, PciExtCapabilityKind, PciHeaderType,
+    PciL1Substates, PciMsi, PciMsiX, PciPowerManagement, PciSrIov, PciStatus,
+};
+mod config;
+
+pub use self::control::PciPowerState;
+mod control;
+
+pub use self::link::{PciAerCounters, PciLinkSpeed, PciLinkStatus};
+mod link;
+
+pub use self::rebind::PciRebind;
+mod rebind;
+
+pub use self::resource::{PciResource, PciResourceMap};
+mod resource;
+
+pub use self::rom::{PciRomCodeType, PciRomImage};
+mod rom;
+
+pub use self::slot::PciSlot;
+mod slot;
+
+mod sriov;
+
+pub use self::topology::{PciAddress, PciRootBus, PciTopology, PciTopologyNode};
+mod topology;
+
+pub use self::vpd::{PciVpd, PciVpdField};
+mod vpd;
+
+#[cfg(feature = "pci-ids")]
+pub use self::ids::{PciDeviceNames, PciIds};
+#[cfg(feature = "pci-ids")]
+mod ids;
+
+#[derive(Clone, Debug)]
 pub struct PciDriver {
     path: PathBuf,
 }
@@ -27,13 +65,68 @@ impl SysClass for PciDriver {
 }
 
 impl PciDriver {
+    /// Binds `device` to this driver.
+    ///
+    /// # Safety
+    ///
+    /// Binding a device to the wrong driver can leave the hardware in an undefined state.
     pub unsafe fn bind(&self, device: &PciDevice) -> io::Result<()> {
         self.write_file("bind", device.id())
     }
 
+    /// Unbinds `device` from this driver.
+    ///
+    /// # Safety
+    ///
+    /// The device will no longer be usable until it is bound to another driver.
     pub unsafe fn unbind(&self, device: &PciDevice) -> io::Result<()> {
         self.write_file("unbind", device.id())
     }
+
+    /// Devices currently bound to this driver.
+    pub fn devices(&self) -> io::Result<Vec<PciDevice>> {
+        let mut devices = Vec::new();
+        for entry in fs::read_dir(&self.path)? {
+            let entry = entry?;
+            // Bound devices are linked by their address, such as `0000:00:1f.2`.
+            let is_device = entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| name.contains(':'));
+            if is_device {
+                devices.push(PciDevice::new(&entry.file_name().to_string_lossy())?);
+            }
+        }
+
+        devices.sort_unstable_by(|a, b| a.id().cmp(b.id()));
+        Ok(devices)
+    }
+
+    /// Name of the kernel module which provides the driver. Drivers which are built into
+    /// the kernel may not have a module.
+    pub fn module(&self) -> io::Result<String> {
+        let path = fs::canonicalize(self.path.join("module"))?;
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .map(str::to_owned)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}: invalid module link", path.display()),
+                )
+            })
+    }
+
+    /// Adds a vendor and device ID to the IDs handled by this driver, which also binds
+    /// any matching devices without a driver.
+    pub fn new_id(&self, vendor: u16, device: u16) -> io::Result<()> {
+        self.write_file("new_id", format!("{:04x} {:04x}", vendor, device))
+    }
+
+    /// Removes a vendor and device ID previously added with `new_id`.
+    pub fn remove_id(&self, vendor: u16, device: u16) -> io::Result<()> {
+        self.write_file("remove_id", format!("{:04x} {:04x}", vendor, device))
+    }
 }
 
 macro_rules! pci_devices {
@@ -52,7 +145,7 @@ macro_rules! pci_devices {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct PciDevice {
     path: PathBuf,
 }
@@ -85,10 +178,33 @@ impl PciDevice {
         fn vendor -> u16;
     }
 
+    /// Reads the configuration space of the device. Only root can read past the first
+    /// 64 bytes of the standard header.
+    pub fn config(&self) -> io::Result<PciConfig> {
+        fs::read(self.path.join("config")).map(PciConfig::from_bytes)
+    }
+
+    /// Resolves a symbolic link underneath the device which points to another PCI device.
+    pub(crate) fn resolve_link<P: AsRef<Path>>(&self, name: P) -> io::Result<PciDevice> {
+        let path = fs::canonicalize(self.path.join(name))?;
+        match path.file_name().and_then(|name| name.to_str()) {
+            Some(_) => Ok(unsafe { PciDevice::from_path_unchecked(path) }),
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: invalid link", path.display()),
+            )),
+        }
+    }
+
     pub fn driver(&self) -> io::Result<PciDriver> {
         fs::canonicalize(self.path.join("driver")).map(|path| PciDriver { path })
     }
 
+    /// Removes the device from the PCI bus.
+    ///
+    /// # Safety
+    ///
+    /// The device will disappear from the system until the bus is rescanned.
     pub unsafe fn remove(&self) -> io::Result<()> {
         self.write_file("remove", "1")
     }
@@ -99,3 +215,5 @@ impl RuntimePM for PciDevice {
         self.write_file("power/control", <&'static str>::from(state))
     }
 }
+
+impl RuntimePMExt for PciDevice {}
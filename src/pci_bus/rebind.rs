@@ -0,0 +1,243 @@
+use super::{PciDevice, PciDriver};
+use crate::SysClass;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+
+impl PciDevice {
+    /// The driver which is forced to bind to this device, if any.
+    pub fn driver_override(&self) -> Result<Option<String>> {
+        let value = self.trim_file("driver_override")?;
+        Ok(if value == "(null)" || value.is_empty() {
+            None
+        } else {
+            Some(value)
+        })
+    }
+
+    /// Forces only the named driver to bind to this device on the next probe.
+    pub fn set_driver_override(&self, driver: &str) -> Result<()> {
+        self.write_file("driver_override", driver)
+    }
+
+    pub fn clear_driver_override(&self) -> Result<()> {
+        self.write_file("driver_override", "\n")
+    }
+
+    /// Asks the kernel to bind a driver to this device, honouring any driver override.
+    pub fn drivers_probe(&self) -> Result<()> {
+        let path = PciDevice::dir()
+            .parent()
+            .map(|bus| bus.join("drivers_probe"))
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, "drivers_probe"))?;
+        fs::write(path, self.id())
+    }
+
+    /// Moves the device from its current driver to `driver`, such as `vfio-pci`.
+    ///
+    /// The device is given a driver override, unbound from its current driver, and probed
+    /// again. If it does not end up bound to `driver`, the original driver and override
+    /// are restored and an error is returned. On success, the returned value can be used
+    /// to move the device back.
+    ///
+    /// # Safety
+    ///
+    /// The device is unbound from its current driver. The caller must ensure that nothing
+    /// is using the device through that driver, such as a mounted filesystem or a network
+    /// interface, and that nothing else changes the binding of the device until the
+    /// returned `PciRebind` is restored or dropped.
+    pub unsafe fn rebind(&self, driver: &str) -> Result<PciRebind> {
+        let rebind = PciRebind {
+            device: self.clone(),
+            previous_driver: self.driver().ok().map(|driver| driver.id().to_owned()),
+            previous_override: self.driver_override()?,
+        };
+
+        if rebind.previous_driver.as_deref() == Some(driver) {
+            return Ok(rebind);
+        }
+
+        if let Err(why) = rebind.bind_to(driver) {
+            // The original error is more useful than any failure to restore.
+            let _ = rebind.restore_ref();
+            return Err(why);
+        }
+
+        Ok(rebind)
+    }
+}
+
+/// Record of the driver binding of a device before it was moved with `PciDevice::rebind`.
+///
+/// Dropping it leaves the device bound to the new driver. Moving the device back with
+/// `restore` is unsafe for the same reason as moving it away: the caller must ensure that
+/// nothing is still using the device through the driver it is bound to at that point.
+#[must_use = "the previous driver is only restored by calling `restore`"]
+pub struct PciRebind {
+    device: PciDevice,
+    previous_driver: Option<String>,
+    previous_override: Option<String>,
+}
+
+impl PciRebind {
+    pub fn device(&self) -> &PciDevice {
+        &self.device
+    }
+
+    /// The driver the device was bound to before it was moved.
+    pub fn previous_driver(&self) -> Option<&str> {
+        self.previous_driver.as_deref()
+    }
+
+    /// The driver override the device had before it was moved.
+    pub fn previous_override(&self) -> Option<&str> {
+        self.previous_override.as_deref()
+    }
+
+    /// Moves the device back to its previous driver and driver override.
+    ///
+    /// # Safety
+    ///
+    /// The device is unbound from the driver it is currently bound to. The caller must
+    /// ensure that nothing is using the device through that driver, such as a process
+    /// holding the VFIO group open, and that nothing else changes the binding of the
+    /// device meanwhile.
+    pub unsafe fn restore(self) -> Result<()> {
+        self.restore_ref()
+    }
+
+    /// # Safety
+    ///
+    /// Same contract as `PciDevice::rebind`.
+    unsafe fn bind_to(&self, driver: &str) -> Result<()> {
+        let device = &self.device;
+
+        device.set_driver_override(driver)?;
+        if let Ok(current) = device.driver() {
+            current.unbind(device)?;
+        }
+
+        // A failed probe is reported below, with the driver that was bound instead.
+        let probed = device.drivers_probe();
+
+        match device.driver() {
+            Ok(bound) if bound.id() == driver => Ok(()),
+            Ok(bound) => Err(Error::other(format!(
+                "{}: bound to {} instead of {}",
+                device.id(),
+                bound.id(),
+                driver
+            ))),
+            Err(_) => Err(probed.err().unwrap_or_else(|| {
+                Error::new(
+                    ErrorKind::NotFound,
+                    format!("{}: {} did not bind to the device", device.id(), driver),
+                )
+            })),
+        }
+    }
+
+    /// # Safety
+    ///
+    /// Same contract as `restore`.
+    unsafe fn restore_ref(&self) -> Result<()> {
+        let device = &self.device;
+
+        match self.previous_override {
+            Some(ref driver) => device.set_driver_override(driver)?,
+            None => device.clear_driver_override()?,
+        }
+
+        if let Ok(current) = device.driver() {
+            if Some(current.id()) == self.previous_driver.as_deref() {
+                return Ok(());
+            }
+
+            current.unbind(device)?;
+        }
+
+        match self.previous_driver {
+            Some(ref driver) => PciDriver::new(driver)?.bind(device),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use std::os::unix::fs::symlink;
+
+    fn fixture(name: &str, driver: &str, driver_override: &str) -> (TempDir, PciDevice) {
+        let dir = TempDir::new(name);
+        let drivers = dir.path().join("drivers").join(driver);
+        fs::create_dir_all(&drivers).unwrap();
+        let path = dir.path().join("0000:01:00.0");
+        fs::create_dir(&path).unwrap();
+        symlink(&drivers, path.join("driver")).unwrap();
+        fs::write(path.join("driver_override"), driver_override).unwrap();
+
+        let device = unsafe { PciDevice::from_path_unchecked(path) };
+        (dir, device)
+    }
+
+    #[test]
+    fn driver_override() {
+        let (_dir, device) = fixture("pci-rebind-override", "nvme", "(null)\n");
+        assert_eq!(device.driver_override().unwrap(), None);
+
+        fs::write(device.path().join("driver_override"), "\n").unwrap();
+        assert_eq!(device.driver_override().unwrap(), None);
+
+        fs::write(device.path().join("driver_override"), "vfio-pci\n").unwrap();
+        assert_eq!(
+            device.driver_override().unwrap().as_deref(),
+            Some("vfio-pci")
+        );
+    }
+
+    #[test]
+    fn already_bound() {
+        let (_dir, device) = fixture("pci-rebind-bound", "vfio-pci", "vfio-pci\n");
+
+        // The device is already bound to the requested driver, so nothing is written.
+        let rebind = unsafe { device.rebind("vfio-pci").unwrap() };
+        assert_eq!(rebind.device().id(), "0000:01:00.0");
+        assert_eq!(rebind.previous_driver(), Some("vfio-pci"));
+        assert_eq!(rebind.previous_override(), Some("vfio-pci"));
+        assert_eq!(
+            fs::read_to_string(device.path().join("driver_override")).unwrap(),
+            "vfio-pci\n"
+        );
+    }
+
+    #[test]
+    fn restore() {
+        let (_dir, device) = fixture("pci-rebind-restore", "nvme", "(null)\n");
+        let rebind = PciRebind {
+            device: device.clone(),
+            previous_driver: Some("nvme".into()),
+            previous_override: None,
+        };
+
+        // The device is still bound to its previous driver, so only the override is reset.
+        fs::write(device.path().join("driver_override"), "").unwrap();
+        unsafe { rebind.restore().unwrap() };
+        assert_eq!(
+            fs::read_to_string(device.path().join("driver_override")).unwrap(),
+            "\n"
+        );
+
+        let rebind = PciRebind {
+            device: device.clone(),
+            previous_driver: Some("nvme".into()),
+            previous_override: Some("nvme".into()),
+        };
+        fs::write(device.path().join("driver_override"), "").unwrap();
+        unsafe { rebind.restore().unwrap() };
+        assert_eq!(
+            fs::read_to_string(device.path().join("driver_override")).unwrap(),
+            "nvme"
+        );
+    }
+}
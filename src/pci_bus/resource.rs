@@ -0,0 +1,428 @@
+use super::PciDevice;
+use crate::SysClass;
+use std::fs::{File, OpenOptions};
+use std::io::{Error, ErrorKind, Result};
+use std::os::unix::io::AsRawFd;
+use std::ptr;
+use std::str::FromStr;
+
+// Resource flags. Copied from kernel header.
+// https://git.kernel.org/pub/scm/linux/kernel/git/torvalds/linux.git/tree/include/linux/ioport.h
+const IORESOURCE_IO: u64 = 0x0000_0100;
+const IORESOURCE_MEM: u64 = 0x0000_0200;
+const IORESOURCE_PREFETCH: u64 = 0x0000_2000;
+const IORESOURCE_MEM_64: u64 = 0x0010_0000;
+
+/// A resource of a PCI device, as listed in its `resource` file.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PciResource {
+    /// Line of the `resource` file, which is the BAR number for the first six entries.
+    pub index: u8,
+    pub start: u64,
+    pub end: u64,
+    pub flags: u64,
+}
+
+impl PciResource {
+    /// Length of the range in bytes. Unassigned and malformed ranges, which end before
+    /// they start, are empty.
+    pub fn size(&self) -> u64 {
+        if self.end == 0 && self.start == 0 {
+            0
+        } else {
+            match self.end.checked_sub(self.start) {
+                Some(last) => last.saturating_add(1),
+                None => 0,
+            }
+        }
+    }
+
+    pub fn is_io(&self) -> bool {
+        self.flags & IORESOURCE_IO != 0
+    }
+
+    pub fn is_memory(&self) -> bool {
+        self.flags & IORESOURCE_MEM != 0
+    }
+
+    pub fn is_prefetchable(&self) -> bool {
+        self.flags & IORESOURCE_PREFETCH != 0
+    }
+
+    pub fn is_64bit(&self) -> bool {
+        self.flags & IORESOURCE_MEM_64 != 0
+    }
+}
+
+impl FromStr for PciResource {
+    type Err = Error;
+
+    /// Parses a line of the `resource` file, leaving the index as zero.
+    fn from_str(s: &str) -> Result<Self> {
+        let mut fields = s.split_whitespace().map(|field| {
+            field
+                .strip_prefix("0x")
+                .and_then(|field| u64::from_str_radix(field, 16).ok())
+                .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("resource: {}", s)))
+        });
+
+        let mut next = || {
+            fields.next().unwrap_or_else(|| {
+                Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("resource: {}", s),
+                ))
+            })
+        };
+
+        Ok(PciResource {
+            index: 0,
+            start: next()?,
+            end: next()?,
+            flags: next()?,
+        })
+    }
+}
+
+impl PciDevice {
+    /// Resources which are assigned to the device. Unused entries are skipped.
+    pub fn resources(&self) -> Result<Vec<PciResource>> {
+        let mut resources = Vec::new();
+        for (index, line) in self.read_file("resource")?.lines().enumerate() {
+            let mut resource = line.parse::<PciResource>()?;
+            if resource.flags == 0 {
+                continue;
+            }
+
+            resource.index = index as u8;
+            resources.push(resource);
+        }
+
+        Ok(resources)
+    }
+
+    /// Maps a memory BAR of the device, using write combining if `write_combining` is set.
+    /// Write combining is only available for prefetchable BARs.
+    ///
+    /// # Safety
+    ///
+    /// Reads and writes of device registers can have side effects on the device, and can
+    /// conflict with a driver which is bound to it.
+    pub unsafe fn map_resource(&self, bar: u8, write_combining: bool) -> Result<PciResourceMap> {
+        let name = if write_combining {
+            format!("resource{}_wc", bar)
+        } else {
+            format!("resource{}", bar)
+        };
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(self.path().join(name))?;
+
+        PciResourceMap::new(&file)
+    }
+
+    /// BAR sizes supported by the resizable BAR capability, as a bitmask where bit `n`
+    /// means `1 MiB << n`.
+    pub fn resource_resize(&self, bar: u8) -> Result<u64> {
+        let value = self.trim_file(format!("resource{}_resize", bar))?;
+        u64::from_str_radix(&value, 16)
+            .map_err(|err| Error::new(ErrorKind::InvalidData, format!("{}", err)))
+    }
+
+    /// BAR sizes supported by the resizable BAR capability, in bytes.
+    pub fn resource_resize_sizes(&self, bar: u8) -> Result<Vec<u64>> {
+        let mask = self.resource_resize(bar)?;
+        Ok((0..44)
+            .filter(|bit| mask & (1 << bit) != 0)
+            .map(|bit| (1 << 20) << bit)
+            .collect())
+    }
+
+    /// Resizes a BAR to `1 MiB << size`. No driver may be bound to the device.
+    ///
+    /// # Safety
+    ///
+    /// All resources of the device are reassigned, and existing mappings become invalid.
+    pub unsafe fn set_resource_resize(&self, bar: u8, size: u8) -> Result<()> {
+        self.write_file(format!("resource{}_resize", bar), size.to_string())
+    }
+}
+
+/// A memory-mapped PCI resource, with bounds-checked volatile access.
+pub struct PciResourceMap {
+    ptr: *mut u8,
+    len: usize,
+}
+
+impl PciResourceMap {
+    /// Maps the whole of `file`, which is usually a `resourceN` file but may be any
+    /// regular file.
+    ///
+    /// # Safety
+    ///
+    /// Nothing else may map or modify the file in a way that violates the expectations
+    /// of the caller, and accesses to device files can have side effects on the device.
+    pub unsafe fn new(file: &File) -> Result<Self> {
+        let len = file.metadata()?.len() as usize;
+        if len == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "resource is empty"));
+        }
+
+        let ptr = libc::mmap(
+            ptr::null_mut(),
+            len,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_SHARED,
+            file.as_raw_fd(),
+            0,
+        );
+
+        if ptr == libc::MAP_FAILED {
+            return Err(Error::last_os_error());
+        }
+
+        Ok(Self {
+            ptr: ptr as *mut u8,
+            len,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn check<T>(&self, offset: usize) -> Result<*mut T> {
+        let size = std::mem::size_of::<T>();
+        if !offset.is_multiple_of(size) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("offset {:#x} is not aligned to {} bytes", offset, size),
+            ));
+        }
+
+        match offset.checked_add(size) {
+            Some(end) if end <= self.len => Ok(unsafe { self.ptr.add(offset) } as *mut T),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("offset {:#x} is outside of {} bytes", offset, self.len),
+            )),
+        }
+    }
+
+    pub fn read_u8(&self, offset: usize) -> Result<u8> {
+        self.check(offset)
+            .map(|ptr| unsafe { ptr::read_volatile(ptr) })
+    }
+
+    pub fn read_u16(&self, offset: usize) -> Result<u16> {
+        self.check(offset)
+            .map(|ptr| unsafe { ptr::read_volatile(ptr) })
+    }
+
+    pub fn read_u32(&self, offset: usize) -> Result<u32> {
+        self.check(offset)
+            .map(|ptr| unsafe { ptr::read_volatile(ptr) })
+    }
+
+    pub fn read_u64(&self, offset: usize) -> Result<u64> {
+        self.check(offset)
+            .map(|ptr| unsafe { ptr::read_volatile(ptr) })
+    }
+
+    pub fn write_u8(&self, offset: usize, value: u8) -> Result<()> {
+        self.check(offset)
+            .map(|ptr| unsafe { ptr::write_volatile(ptr, value) })
+    }
+
+    pub fn write_u16(&self, offset: usize, value: u16) -> Result<()> {
+        self.check(offset)
+            .map(|ptr| unsafe { ptr::write_volatile(ptr, value) })
+    }
+
+    pub fn write_u32(&self, offset: usize, value: u32) -> Result<()> {
+        self.check(offset)
+            .map(|ptr| unsafe { ptr::write_volatile(ptr, value) })
+    }
+
+    pub fn write_u64(&self, offset: usize, value: u64) -> Result<()> {
+        self.check(offset)
+            .map(|ptr| unsafe { ptr::write_volatile(ptr, value) })
+    }
+}
+
+impl Drop for PciResourceMap {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.ptr as *mut libc::c_void, self.len);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use std::fs;
+
+    fn map(dir: &TempDir, data: &[u8]) -> Result<PciResourceMap> {
+        let path = dir.path().join("resource0");
+        fs::write(&path, data).unwrap();
+        let file = OpenOptions::new().read(true).write(true).open(&path)?;
+        unsafe { PciResourceMap::new(&file) }
+    }
+
+    #[test]
+    fn parse_resource() {
+        let resource = "0x00000000fe000000 0x00000000fe003fff 0x0000000000140204"
+            .parse::<PciResource>()
+            .unwrap();
+        assert_eq!(resource.start, 0xfe00_0000);
+        assert_eq!(resource.end, 0xfe00_3fff);
+        assert_eq!(resource.size(), 0x4000);
+        assert!(resource.is_memory());
+        assert!(resource.is_64bit());
+        assert!(!resource.is_prefetchable());
+        assert!(!resource.is_io());
+
+        let unused = "0x0000000000000000 0x0000000000000000 0x0000000000000000"
+            .parse::<PciResource>()
+            .unwrap();
+        assert_eq!(unused.size(), 0);
+
+        let resource = |start, end| PciResource {
+            index: 0,
+            start,
+            end,
+            flags: IORESOURCE_MEM,
+        };
+        assert_eq!(resource(0x1000, 0x0fff).size(), 0);
+        assert_eq!(resource(0, u64::MAX).size(), u64::MAX);
+        assert_eq!(resource(1, u64::MAX).size(), u64::MAX);
+        assert_eq!(resource(0x1000, 0x1000).size(), 1);
+
+        for invalid in &[
+            "",
+            "0x0 0x0",
+            "0x0 0x0 0xzz",
+            "00000000fe000000 0x00000000fe003fff 0x0000000000140204",
+        ] {
+            let err = invalid.parse::<PciResource>().unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidData, "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn parse_resources() {
+        let dir = TempDir::new("resources");
+        fs::write(
+            dir.path().join("resource"),
+            "0x00000000fe000000 0x00000000fe003fff 0x0000000000140204\n\
+             0x0000000000000000 0x0000000000000000 0x0000000000000000\n\
+             0x000000000000c000 0x000000000000c03f 0x0000000000040101\n\
+             0x00000000c0000000 0x00000000cfffffff 0x000000000014220c\n",
+        )
+        .unwrap();
+
+        let device = unsafe { PciDevice::from_path_unchecked(dir.path().to_owned()) };
+        let resources = device.resources().unwrap();
+        let summary = resources
+            .iter()
+            .map(|resource| (resource.index, resource.size()))
+            .collect::<Vec<_>>();
+        assert_eq!(summary, vec![(0, 0x4000), (2, 0x40), (3, 0x1000_0000)]);
+        assert!(resources[1].is_io());
+        assert!(resources[2].is_prefetchable());
+
+        fs::write(dir.path().join("resource"), "garbage\n").unwrap();
+        assert!(device.resources().is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let dir = TempDir::new("resource-round-trip");
+        let map = map(&dir, &[0; 4096]).unwrap();
+        assert_eq!(map.len(), 4096);
+        assert!(!map.is_empty());
+
+        map.write_u8(1, 0xaa).unwrap();
+        map.write_u16(2, 0xbbcc).unwrap();
+        map.write_u32(4, 0xdead_beef).unwrap();
+        map.write_u64(8, 0x0123_4567_89ab_cdef).unwrap();
+        map.write_u32(4092, 0xfeed_f00d).unwrap();
+
+        assert_eq!(map.read_u8(1).unwrap(), 0xaa);
+        assert_eq!(map.read_u16(2).unwrap(), 0xbbcc);
+        assert_eq!(map.read_u32(4).unwrap(), 0xdead_beef);
+        assert_eq!(map.read_u64(8).unwrap(), 0x0123_4567_89ab_cdef);
+        assert_eq!(map.read_u32(0).unwrap(), 0xbbcc_aa00);
+        assert_eq!(map.read_u32(4092).unwrap(), 0xfeed_f00d);
+        drop(map);
+
+        // The mapping is shared, so writes reach the file.
+        let data = fs::read(dir.path().join("resource0")).unwrap();
+        assert_eq!(&data[4..8], &0xdead_beef_u32.to_le_bytes());
+    }
+
+    #[test]
+    fn bounds() {
+        let dir = TempDir::new("resource-bounds");
+        let map = map(&dir, &[0; 4096]).unwrap();
+
+        assert!(map.read_u8(4095).is_ok());
+        assert!(map.read_u64(4088).is_ok());
+        for result in &[
+            map.read_u8(4096).map(|_| ()),
+            map.read_u16(4096).map(|_| ()),
+            map.read_u32(4096).map(|_| ()),
+            map.read_u64(4096).map(|_| ()),
+            map.write_u64(8192, 0),
+        ] {
+            assert_eq!(result.as_ref().unwrap_err().kind(), ErrorKind::InvalidInput);
+        }
+    }
+
+    #[test]
+    fn misaligned() {
+        let dir = TempDir::new("resource-misaligned");
+        let map = map(&dir, &[0; 4096]).unwrap();
+
+        assert!(map.read_u8(3).is_ok());
+        for result in &[
+            map.read_u16(1).map(|_| ()),
+            map.read_u32(2).map(|_| ()),
+            map.read_u64(4).map(|_| ()),
+            map.write_u16(3, 0),
+            map.write_u32(6, 0),
+            map.write_u64(12, 0),
+        ] {
+            let err = result.as_ref().unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidInput);
+            assert!(err.to_string().contains("aligned"));
+        }
+    }
+
+    #[test]
+    fn offset_overflow() {
+        let dir = TempDir::new("resource-overflow");
+        let map = map(&dir, &[0; 4096]).unwrap();
+
+        // Aligned offsets where adding the size overflows.
+        let err = map.read_u64(usize::MAX - 7).unwrap_err();
+        assert!(err.to_string().contains("outside"));
+        let err = map.write_u8(usize::MAX, 0).unwrap_err();
+        assert!(err.to_string().contains("outside"));
+    }
+
+    #[test]
+    fn empty_file() {
+        let dir = TempDir::new("resource-empty");
+        let err = map(&dir, &[]).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,216 @@
+use super::PciDevice;
+use crate::SysClass;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+
+const ROM_SIGNATURE: [u8; 2] = [0x55, 0xaa];
+const PCIR_SIGNATURE: &[u8] = b"PCIR";
+const ROM_LAST_IMAGE: u8 = 0x80;
+
+/// The type of code in an expansion ROM image.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PciRomCodeType {
+    X86,
+    OpenFirmware,
+    PaRisc,
+    Efi,
+    Other(u8),
+}
+
+impl From<u8> for PciRomCodeType {
+    fn from(value: u8) -> Self {
+        match value {
+            0x00 => PciRomCodeType::X86,
+            0x01 => PciRomCodeType::OpenFirmware,
+            0x02 => PciRomCodeType::PaRisc,
+            0x03 => PciRomCodeType::Efi,
+            other => PciRomCodeType::Other(other),
+        }
+    }
+}
+
+/// An image of a PCI expansion ROM, described by its ROM header and PCI data structure.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PciRomImage {
+    /// Offset of the image within the ROM.
+    pub offset: usize,
+    /// Length of the image in bytes.
+    pub length: usize,
+    pub vendor: u16,
+    pub device: u16,
+    pub class: u32,
+    /// Revision of the PCI data structure.
+    pub revision: u8,
+    /// Revision of the code or data in the image.
+    pub code_revision: u16,
+    pub code_type: PciRomCodeType,
+}
+
+impl PciRomImage {
+    /// Parses the images of an expansion ROM, up to the image marked as the last one.
+    pub fn parse_all(rom: &[u8]) -> Result<Vec<Self>> {
+        let mut images = Vec::new();
+        let mut offset = 0;
+        loop {
+            let (image, last) = Self::parse(rom, offset)?;
+            offset += image.length;
+            images.push(image);
+            if last || offset >= rom.len() {
+                return Ok(images);
+            }
+        }
+    }
+
+    fn parse(rom: &[u8], offset: usize) -> Result<(Self, bool)> {
+        let invalid = |what: &str| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("ROM image at {:#x}: {}", offset, what),
+            )
+        };
+
+        let read_u16 = |at: usize| {
+            rom.get(at..at + 2)
+                .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+                .ok_or_else(|| invalid("truncated"))
+        };
+
+        if rom.get(offset..offset + 2) != Some(&ROM_SIGNATURE[..]) {
+            return Err(invalid("missing ROM signature"));
+        }
+
+        let pcir = offset + usize::from(read_u16(offset + 0x18)?);
+        let data = rom
+            .get(pcir..pcir + 0x18)
+            .ok_or_else(|| invalid("truncated"))?;
+        if &data[..4] != PCIR_SIGNATURE {
+            return Err(invalid("missing PCIR signature"));
+        }
+
+        let length = usize::from(read_u16(pcir + 0x10)?) * 512;
+        if length == 0 {
+            return Err(invalid("image length is zero"));
+        }
+
+        let image = PciRomImage {
+            offset,
+            length,
+            vendor: read_u16(pcir + 0x04)?,
+            device: read_u16(pcir + 0x06)?,
+            class: u32::from_le_bytes([data[0x0d], data[0x0e], data[0x0f], 0]),
+            revision: data[0x0c],
+            code_revision: read_u16(pcir + 0x12)?,
+            code_type: PciRomCodeType::from(data[0x14]),
+        };
+
+        Ok((image, data[0x15] & ROM_LAST_IMAGE != 0))
+    }
+}
+
+impl PciDevice {
+    /// Reads the expansion ROM of the device. The ROM is enabled for the read, and disabled
+    /// again afterwards. Only root can read it.
+    pub fn rom(&self) -> Result<Vec<u8>> {
+        self.write_file("rom", "1")?;
+        let rom = fs::read(self.path.join("rom"));
+        let disabled = self.write_file("rom", "0");
+        let rom = rom?;
+        disabled?;
+        Ok(rom)
+    }
+
+    /// Reads the expansion ROM of the device and parses its images.
+    pub fn rom_images(&self) -> Result<Vec<PciRomImage>> {
+        PciRomImage::parse_all(&self.rom()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An image of `blocks` 512-byte blocks, with its PCI data structure at 0x1c.
+    fn image(blocks: u16, code_type: u8, last: bool) -> Vec<u8> {
+        let mut image = vec![0; usize::from(blocks) * 512];
+        image[..2].copy_from_slice(&ROM_SIGNATURE);
+        image[0x18..0x1a].copy_from_slice(&0x1cu16.to_le_bytes());
+
+        let pcir = &mut image[0x1c..0x1c + 0x18];
+        pcir[..4].copy_from_slice(PCIR_SIGNATURE);
+        pcir[0x04..0x06].copy_from_slice(&0x8086u16.to_le_bytes());
+        pcir[0x06..0x08].copy_from_slice(&0x15f3u16.to_le_bytes());
+        pcir[0x0c] = 3;
+        pcir[0x0d..0x10].copy_from_slice(&[0x00, 0x00, 0x02]);
+        pcir[0x10..0x12].copy_from_slice(&blocks.to_le_bytes());
+        pcir[0x12..0x14].copy_from_slice(&0x0102u16.to_le_bytes());
+        pcir[0x14] = code_type;
+        pcir[0x15] = if last { ROM_LAST_IMAGE } else { 0 };
+        image
+    }
+
+    #[test]
+    fn parse_chain() {
+        let mut rom = [image(1, 0x00, false), image(2, 0x03, true)].concat();
+        // Padding after the last image is not parsed.
+        rom.extend_from_slice(&[0xff; 512]);
+
+        let images = PciRomImage::parse_all(&rom).unwrap();
+        assert_eq!(
+            images,
+            [
+                PciRomImage {
+                    offset: 0,
+                    length: 512,
+                    vendor: 0x8086,
+                    device: 0x15f3,
+                    class: 0x020000,
+                    revision: 3,
+                    code_revision: 0x0102,
+                    code_type: PciRomCodeType::X86,
+                },
+                PciRomImage {
+                    offset: 512,
+                    length: 1024,
+                    vendor: 0x8086,
+                    device: 0x15f3,
+                    class: 0x020000,
+                    revision: 3,
+                    code_revision: 0x0102,
+                    code_type: PciRomCodeType::Efi,
+                },
+            ]
+        );
+
+        // The chain also ends at the end of the ROM, without a last image flag.
+        let rom = image(1, 0x01, false);
+        let images = PciRomImage::parse_all(&rom).unwrap();
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].code_type, PciRomCodeType::OpenFirmware);
+        assert_eq!(PciRomCodeType::from(0x70), PciRomCodeType::Other(0x70));
+    }
+
+    #[test]
+    fn invalid() {
+        let invalid = |rom: &[u8]| PciRomImage::parse_all(rom).unwrap_err().kind();
+
+        assert_eq!(invalid(&[]), ErrorKind::InvalidData);
+        assert_eq!(invalid(&[0xff; 512]), ErrorKind::InvalidData);
+
+        // Truncated before and inside the PCI data structure.
+        let rom = image(1, 0x00, true);
+        assert_eq!(invalid(&rom[..0x10]), ErrorKind::InvalidData);
+        assert_eq!(invalid(&rom[..0x20]), ErrorKind::InvalidData);
+
+        let mut rom = image(1, 0x00, true);
+        rom[0x1c] = b'X';
+        assert_eq!(invalid(&rom), ErrorKind::InvalidData);
+
+        let mut rom = image(1, 0x00, true);
+        rom[0x1c + 0x10] = 0;
+        assert_eq!(invalid(&rom), ErrorKind::InvalidData);
+
+        // The next image in the chain is missing its signature.
+        let rom = [image(1, 0x00, false), vec![0; 512]].concat();
+        assert_eq!(invalid(&rom), ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,165 @@
+use super::{PciAddress, PciDevice};
+use crate::SysClass;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+/// A physical PCI slot, which may support hotplug.
+#[derive(Clone, Debug)]
+pub struct PciSlot {
+    path: PathBuf,
+}
+
+impl SysClass for PciSlot {
+    fn base() -> &'static str {
+        "bus"
+    }
+
+    fn class() -> &'static str {
+        "pci/slots"
+    }
+
+    unsafe fn from_path_unchecked(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl PciSlot {
+    /// Address of the slot, as in `0000:01:00`. The device number is omitted by hotplug
+    /// drivers which do not know it, in which case every device on the bus is in the slot.
+    pub fn address(&self) -> Result<String> {
+        self.trim_file("address")
+    }
+
+    /// Whether the slot contains a device which matches `address`.
+    pub fn contains(&self, address: PciAddress) -> Result<bool> {
+        let slot = self.address()?;
+        let invalid = || {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid slot address: {}", slot),
+            )
+        };
+
+        let mut fields = slot.split(':');
+        let domain = fields.next().ok_or_else(invalid)?;
+        let bus = fields.next().ok_or_else(invalid)?;
+        let device = fields.next();
+
+        let domain = u32::from_str_radix(domain, 16).map_err(|_| invalid())?;
+        let bus = u8::from_str_radix(bus, 16).map_err(|_| invalid())?;
+        let device = match device {
+            Some(device) => Some(u8::from_str_radix(device, 16).map_err(|_| invalid())?),
+            None => None,
+        };
+
+        Ok(domain == address.domain
+            && bus == address.bus
+            && device.is_none_or(|device| device == address.device))
+    }
+
+    /// Devices in the slot, which are the functions of the adapter that is plugged in.
+    pub fn devices(&self) -> Result<Vec<PciDevice>> {
+        let mut devices = Vec::new();
+        for device in PciDevice::all()? {
+            if self.contains(device.address()?)? {
+                devices.push(device);
+            }
+        }
+
+        devices.sort_unstable_by(|a, b| a.id().cmp(b.id()));
+        Ok(devices)
+    }
+
+    method!(power parse_file u8);
+
+    /// Powers the slot on or off. The devices in the slot are removed before it is
+    /// powered off, and probed after it is powered on.
+    ///
+    /// # Safety
+    ///
+    /// The devices in the slot disappear from the system while it is powered off.
+    pub unsafe fn set_power(&self, power: bool) -> Result<()> {
+        self.write_file("power", if power { "1" } else { "0" })
+    }
+
+    /// State of the attention indicator: 0 for off, 1 for on and 2 for blinking.
+    pub fn attention(&self) -> Result<u8> {
+        self.parse_file("attention")
+    }
+
+    set_method!("attention", set_attention u8);
+
+    /// Whether the retention latch of the slot is closed.
+    pub fn latch(&self) -> Result<u8> {
+        self.parse_file("latch")
+    }
+
+    /// Whether an adapter is present in the slot.
+    pub fn adapter(&self) -> Result<u8> {
+        self.parse_file("adapter")
+    }
+
+    method!(cur_bus_speed trim_file String);
+
+    method!(max_bus_speed trim_file String);
+}
+
+impl PciDevice {
+    /// The physical slot holding the device, if any.
+    pub fn slot(&self) -> Result<Option<PciSlot>> {
+        let address = self.address()?;
+        let slots = match PciSlot::all() {
+            Ok(slots) => slots,
+            Err(why) if why.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(why) => return Err(why),
+        };
+
+        for slot in slots {
+            if slot.contains(address)? {
+                return Ok(Some(slot));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use std::fs;
+
+    #[test]
+    fn contains() {
+        let dir = TempDir::new("pci-slot-contains");
+        let slot = unsafe { PciSlot::from_path_unchecked(dir.path().to_path_buf()) };
+        let set_address = |address: &str| {
+            fs::write(dir.path().join("address"), format!("{}\n", address)).unwrap();
+        };
+        let contains = |address: &str| slot.contains(address.parse().unwrap()).unwrap();
+
+        set_address("0000:01:00");
+        assert!(contains("0000:01:00.0"));
+        assert!(contains("0000:01:00.3"));
+        assert!(!contains("0000:01:01.0"));
+        assert!(!contains("0000:02:00.0"));
+        assert!(!contains("0001:01:00.0"));
+
+        // Hotplug drivers which do not know the device number give only the bus.
+        set_address("0000:02");
+        assert!(contains("0000:02:00.0"));
+        assert!(contains("0000:02:1f.7"));
+        assert!(!contains("0000:01:00.0"));
+
+        for address in &["", "0000", "zz:01:00", "0000:01:zz"] {
+            set_address(address);
+            let err = slot.contains("0000:01:00.0".parse().unwrap()).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidData, "{:?}", address);
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use super::PciDevice;
+use crate::SysClass;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+
+impl PciDevice {
+    // Physical function
+
+    method!(sriov_totalvfs parse_file u16);
+
+    method!(sriov_numvfs parse_file u16);
+
+    set_method!("sriov_numvfs", set_sriov_numvfs u16);
+
+    /// Sets the number of enabled virtual functions, disabling existing ones first if needed.
+    pub fn sriov_configure(&self, numvfs: u16) -> Result<()> {
+        let current = self.sriov_numvfs()?;
+        if current == numvfs {
+            return Ok(());
+        }
+
+        if current != 0 {
+            self.set_sriov_numvfs(0)?;
+        }
+
+        if numvfs != 0 {
+            self.set_sriov_numvfs(numvfs)?;
+        }
+
+        Ok(())
+    }
+
+    method!(sriov_drivers_autoprobe parse_file u8);
+
+    set_method!("sriov_drivers_autoprobe", set_sriov_drivers_autoprobe u8);
+
+    method!(sriov_offset parse_file u16);
+
+    method!(sriov_stride parse_file u16);
+
+    /// Device ID of the virtual functions.
+    pub fn sriov_vf_device(&self) -> Result<u16> {
+        let value = self.trim_file("sriov_vf_device")?;
+        u16::from_str_radix(&value, 16)
+            .map_err(|err| Error::new(ErrorKind::InvalidData, format!("{}", err)))
+    }
+
+    method!(sriov_vf_total_msix parse_file u32);
+
+    /// The enabled virtual functions, in order of their index.
+    pub fn virtfns(&self) -> Result<Vec<PciDevice>> {
+        let mut virtfns = Vec::new();
+        for entry in fs::read_dir(&self.path)? {
+            let entry = entry?;
+            let index = match entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.strip_prefix("virtfn"))
+                .and_then(|index| index.parse::<u16>().ok())
+            {
+                Some(index) => index,
+                None => continue,
+            };
+
+            virtfns.push((index, self.resolve_link(entry.file_name())?));
+        }
+
+        virtfns.sort_unstable_by_key(|&(index, _)| index);
+        Ok(virtfns.into_iter().map(|(_, virtfn)| virtfn).collect())
+    }
+
+    /// The virtual function at `index`.
+    pub fn virtfn(&self, index: u16) -> Result<PciDevice> {
+        self.resolve_link(format!("virtfn{}", index))
+    }
+
+    // Virtual function
+
+    /// Whether this device is a virtual function.
+    pub fn is_virtfn(&self) -> bool {
+        self.path.join("physfn").exists()
+    }
+
+    /// The physical function which this virtual function belongs to.
+    pub fn physfn(&self) -> Result<PciDevice> {
+        self.resolve_link("physfn")
+    }
+
+    set_method!("sriov_vf_msix_count", set_sriov_vf_msix_count u32);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use std::os::unix::fs::symlink;
+
+    const PF: &str = "0000:01:00.0";
+    const VFS: &[(u16, &str)] = &[
+        (0, "0000:01:00.2"),
+        (1, "0000:01:00.3"),
+        (10, "0000:01:01.4"),
+    ];
+
+    fn fixture() -> TempDir {
+        let dir = TempDir::new("pci-sriov");
+        let pf = dir.path().join(PF);
+        fs::create_dir(&pf).unwrap();
+        fs::write(pf.join("sriov_totalvfs"), "16\n").unwrap();
+        fs::write(pf.join("sriov_numvfs"), "3\n").unwrap();
+        fs::write(pf.join("sriov_vf_device"), "15c5\n").unwrap();
+        // Other entries which start with the same prefix are not virtual functions.
+        fs::write(pf.join("virtfn_extra"), "").unwrap();
+
+        for (index, name) in VFS {
+            let vf = dir.path().join(name);
+            fs::create_dir(&vf).unwrap();
+            symlink(&pf, vf.join("physfn")).unwrap();
+            symlink(&vf, pf.join(format!("virtfn{}", index))).unwrap();
+        }
+
+        dir
+    }
+
+    fn device(dir: &TempDir, name: &str) -> PciDevice {
+        unsafe { PciDevice::from_path_unchecked(dir.path().join(name)) }
+    }
+
+    #[test]
+    fn virtfns() {
+        let dir = fixture();
+        let pf = device(&dir, PF);
+        assert!(!pf.is_virtfn());
+        assert_eq!(pf.sriov_totalvfs().unwrap(), 16);
+        assert_eq!(pf.sriov_numvfs().unwrap(), 3);
+        assert_eq!(pf.sriov_vf_device().unwrap(), 0x15c5);
+
+        // Sorted by index, not by name: virtfn10 comes after virtfn1.
+        let virtfns = pf.virtfns().unwrap();
+        let ids = virtfns.iter().map(|vf| vf.id()).collect::<Vec<_>>();
+        let expected = VFS.iter().map(|&(_, name)| name).collect::<Vec<_>>();
+        assert_eq!(ids, expected);
+
+        assert_eq!(pf.virtfn(10).unwrap().id(), "0000:01:01.4");
+        assert_eq!(pf.virtfn(2).unwrap_err().kind(), ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn physfn() {
+        let dir = fixture();
+        for &(_, name) in VFS {
+            let vf = device(&dir, name);
+            assert!(vf.is_virtfn());
+            assert_eq!(vf.physfn().unwrap().id(), PF);
+            assert!(vf.virtfns().unwrap().is_empty());
+        }
+
+        assert_eq!(
+            device(&dir, PF).physfn().unwrap_err().kind(),
+            ErrorKind::NotFound
+        );
+    }
+}
@@ -0,0 +1,395 @@
+use super::PciDevice;
+use crate::SysClass;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// The location of a PCI function, as in `0000:00:1f.2`. The domain may be omitted when
+/// parsing, as in `00:1f.2`, in which case it is zero.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub struct PciAddress {
+    pub domain: u32,
+    pub bus: u8,
+    pub device: u8,
+    pub function: u8,
+}
+
+impl FromStr for PciAddress {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid PCI address: {}", s),
+            )
+        };
+
+        let (rest, function) = s.rsplit_once('.').ok_or_else(invalid)?;
+        let mut fields = rest.rsplitn(3, ':');
+        let device = fields.next().ok_or_else(invalid)?;
+        let bus = fields.next().ok_or_else(invalid)?;
+        let domain = fields.next().unwrap_or("0");
+
+        let address = PciAddress {
+            domain: u32::from_str_radix(domain, 16).map_err(|_| invalid())?,
+            bus: u8::from_str_radix(bus, 16).map_err(|_| invalid())?,
+            device: u8::from_str_radix(device, 16).map_err(|_| invalid())?,
+            function: u8::from_str_radix(function, 16).map_err(|_| invalid())?,
+        };
+
+        if address.device > 0x1f || address.function > 0x7 {
+            return Err(invalid());
+        }
+
+        Ok(address)
+    }
+}
+
+impl fmt::Display for PciAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:04x}:{:02x}:{:02x}.{:x}",
+            self.domain, self.bus, self.device, self.function
+        )
+    }
+}
+
+impl PciDevice {
+    pub fn address(&self) -> Result<PciAddress> {
+        self.id().parse()
+    }
+
+    /// The NUMA node closest to the device, or `None` if the system has no NUMA topology.
+    pub fn numa_node(&self) -> Result<Option<u32>> {
+        let node = self.parse_file::<i32, _>("numa_node")?;
+        Ok(u32::try_from(node).ok())
+    }
+
+    method!(local_cpulist trim_file String);
+
+    /// CPUs closest to the device, parsed from `local_cpulist`.
+    pub fn local_cpus(&self) -> Result<Vec<u32>> {
+        parse_cpu_list(&self.local_cpulist()?)
+    }
+
+    /// Bridges between the root complex and the device, starting with the root port.
+    pub fn upstream_bridges(&self) -> Result<Vec<PciDevice>> {
+        let path = fs::canonicalize(&self.path)?;
+        let mut bridges = Vec::new();
+        for ancestor in path.parent().into_iter().flat_map(Path::ancestors) {
+            match ancestor.file_name().and_then(|name| name.to_str()) {
+                Some(name) if name.parse::<PciAddress>().is_ok() => {
+                    bridges.push(unsafe { PciDevice::from_path_unchecked(ancestor.to_path_buf()) })
+                }
+                _ => break,
+            }
+        }
+
+        bridges.reverse();
+        Ok(bridges)
+    }
+
+    /// The bridge directly upstream of the device, if it is not on a root bus.
+    pub fn parent_bridge(&self) -> Result<Option<PciDevice>> {
+        Ok(self.upstream_bridges()?.pop())
+    }
+
+    /// The root port the device is connected through, if it is not on a root bus.
+    pub fn root_port(&self) -> Result<Option<PciDevice>> {
+        Ok(self.upstream_bridges()?.into_iter().next())
+    }
+
+    /// Name of the physical slot holding the device, from /sys/bus/pci/slots.
+    pub fn physical_slot(&self) -> Result<Option<String>> {
+        Ok(self.slot()?.map(|slot| slot.id().to_owned()))
+    }
+}
+
+/// Parses a CPU list such as `0-3,8-11`.
+fn parse_cpu_list(list: &str) -> Result<Vec<u32>> {
+    let invalid = || {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("invalid CPU list: {}", list),
+        )
+    };
+
+    let mut cpus = Vec::new();
+    for range in list.split(',').filter(|range| !range.is_empty()) {
+        let (start, end) = range.split_once('-').unwrap_or((range, range));
+        let start = start.parse::<u32>().map_err(|_| invalid())?;
+        let end = end.parse::<u32>().map_err(|_| invalid())?;
+        cpus.extend(start..=end);
+    }
+
+    Ok(cpus)
+}
+
+/// A device in the PCI topology, with the devices behind it if it is a bridge.
+#[derive(Clone, Debug)]
+pub struct PciTopologyNode {
+    pub device: PciDevice,
+    pub address: PciAddress,
+    pub numa_node: Option<u32>,
+    pub slot: Option<String>,
+    pub children: Vec<PciTopologyNode>,
+}
+
+/// A root bus of the PCI topology, such as `pci0000:00`.
+#[derive(Clone, Debug)]
+pub struct PciRootBus {
+    pub name: String,
+    pub children: Vec<PciTopologyNode>,
+}
+
+/// The tree of PCI devices, from root buses through bridges to endpoints.
+#[derive(Clone, Debug)]
+pub struct PciTopology {
+    pub roots: Vec<PciRootBus>,
+}
+
+impl PciTopology {
+    pub fn new() -> Result<Self> {
+        Self::from_devices(PciDevice::all()?)
+    }
+
+    fn from_devices(devices: Vec<PciDevice>) -> Result<Self> {
+        let mut parents = BTreeMap::<PathBuf, Vec<(PathBuf, PciDevice)>>::new();
+        for device in devices {
+            let path = fs::canonicalize(device.path())?;
+            let parent = path.parent().map(Path::to_path_buf).unwrap_or_default();
+            parents.entry(parent).or_default().push((path, device));
+        }
+
+        // Root buses are the parents which are not PCI devices themselves.
+        let mut roots = Vec::new();
+        for parent in parents.keys() {
+            let name = parent
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or_default();
+            if name.parse::<PciAddress>().is_err() {
+                roots.push((parent.clone(), name.to_owned()));
+            }
+        }
+
+        let roots = roots
+            .into_iter()
+            .map(|(path, name)| {
+                Ok(PciRootBus {
+                    name,
+                    children: Self::children(&mut parents, &path)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(PciTopology { roots })
+    }
+
+    fn children(
+        parents: &mut BTreeMap<PathBuf, Vec<(PathBuf, PciDevice)>>,
+        parent: &Path,
+    ) -> Result<Vec<PciTopologyNode>> {
+        let mut children = Vec::new();
+        for (path, device) in parents.remove(parent).unwrap_or_default() {
+            children.push(PciTopologyNode {
+                address: device.address()?,
+                numa_node: device.numa_node().unwrap_or(None),
+                slot: device.physical_slot().unwrap_or(None),
+                children: Self::children(parents, &path)?,
+                device,
+            });
+        }
+
+        children.sort_unstable_by_key(|node| node.address);
+        Ok(children)
+    }
+
+    /// Finds the node of a device in the tree.
+    pub fn find(&self, address: PciAddress) -> Option<&PciTopologyNode> {
+        fn find(nodes: &[PciTopologyNode], address: PciAddress) -> Option<&PciTopologyNode> {
+            nodes.iter().find_map(|node| {
+                if node.address == address {
+                    Some(node)
+                } else {
+                    find(&node.children, address)
+                }
+            })
+        }
+
+        self.roots
+            .iter()
+            .find_map(|root| find(&root.children, address))
+    }
+}
+
+/// Renders the tree in the manner of `lspci -t`, with one device per line.
+impl fmt::Display for PciTopology {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn write_nodes(
+            f: &mut fmt::Formatter,
+            nodes: &[PciTopologyNode],
+            prefix: &str,
+        ) -> fmt::Result {
+            for (i, node) in nodes.iter().enumerate() {
+                let last = i + 1 == nodes.len();
+                write!(
+                    f,
+                    "{}{}{:02x}:{:02x}.{:x}",
+                    prefix,
+                    if last { "\\-" } else { "+-" },
+                    node.address.bus,
+                    node.address.device,
+                    node.address.function
+                )?;
+                if let Some(ref slot) = node.slot {
+                    write!(f, " [slot {}]", slot)?;
+                }
+                if let Some(numa_node) = node.numa_node {
+                    write!(f, " [node {}]", numa_node)?;
+                }
+                writeln!(f)?;
+
+                let prefix = format!("{}{}", prefix, if last { "  " } else { "| " });
+                write_nodes(f, &node.children, &prefix)?;
+            }
+
+            Ok(())
+        }
+
+        for root in &self.roots {
+            writeln!(f, "-[{}]", root.name.trim_start_matches("pci"))?;
+            write_nodes(f, &root.children, " ")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn address() {
+        let address = "0000:00:1f.2".parse::<PciAddress>().unwrap();
+        assert_eq!(
+            address,
+            PciAddress {
+                domain: 0,
+                bus: 0,
+                device: 0x1f,
+                function: 2,
+            }
+        );
+        assert_eq!(address.to_string(), "0000:00:1f.2");
+
+        let address = "10000:e1:00.7".parse::<PciAddress>().unwrap();
+        assert_eq!(address.domain, 0x10000);
+        assert_eq!(address.to_string(), "10000:e1:00.7");
+
+        // Without a domain, as printed by lspci.
+        let address = "03:00.1".parse::<PciAddress>().unwrap();
+        assert_eq!(address.to_string(), "0000:03:00.1");
+
+        for invalid in &[
+            "",
+            "0000:00:1f",
+            "00.1",
+            "0000:00:20.0",
+            "0000:00:1f.8",
+            "0000:100:00.0",
+            "0:0000:00:1f.2",
+            "pci0000:00",
+            "0000:0g:00.0",
+        ] {
+            let err = invalid.parse::<PciAddress>().unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidData, "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn cpu_list() {
+        assert_eq!(
+            parse_cpu_list("0-3,8,10-11").unwrap(),
+            [0, 1, 2, 3, 8, 10, 11]
+        );
+        assert_eq!(parse_cpu_list("5").unwrap(), [5]);
+        assert!(parse_cpu_list("").unwrap().is_empty());
+
+        for invalid in &["0-", "a", "0-3,x", "1-2-3"] {
+            let err = parse_cpu_list(invalid).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidData, "{:?}", invalid);
+        }
+    }
+
+    /// Builds a tree of device directories, nested as in /sys/devices. A domain which no
+    /// real system has is used, so that no hotplug slot of the host matches the devices.
+    fn fixture(dir: &TempDir) -> Vec<PciDevice> {
+        let devices = [
+            ("pci1234:00/1234:00:01.0", Some(0)),
+            ("pci1234:00/1234:00:01.0/1234:01:00.0", Some(0)),
+            ("pci1234:00/1234:00:01.0/1234:01:00.1", Some(0)),
+            ("pci1234:00/1234:00:1f.0", None),
+            ("pci1234:80/1234:80:02.0", Some(1)),
+        ];
+
+        devices
+            .iter()
+            .map(|(path, numa_node)| {
+                let path = dir.path().join(path);
+                fs::create_dir_all(&path).unwrap();
+                let numa_node = numa_node.map_or(-1, i32::from);
+                fs::write(path.join("numa_node"), format!("{}\n", numa_node)).unwrap();
+                unsafe { PciDevice::from_path_unchecked(path) }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn topology() {
+        let dir = TempDir::new("pci-topology");
+        let topology = PciTopology::from_devices(fixture(&dir)).unwrap();
+
+        assert_eq!(
+            topology.to_string(),
+            concat!(
+                "-[1234:00]\n",
+                " +-00:01.0 [node 0]\n",
+                " | +-01:00.0 [node 0]\n",
+                " | \\-01:00.1 [node 0]\n",
+                " \\-00:1f.0\n",
+                "-[1234:80]\n",
+                " \\-80:02.0 [node 1]\n",
+            )
+        );
+
+        let function = "1234:01:00.1".parse().unwrap();
+        let node = topology.find(function).unwrap();
+        assert_eq!(node.device.id(), "1234:01:00.1");
+        assert!(topology.find("1234:02:00.0".parse().unwrap()).is_none());
+    }
+
+    #[test]
+    fn upstream_bridges() {
+        let dir = TempDir::new("pci-upstream");
+        let devices = fixture(&dir);
+
+        let bridges = devices[2].upstream_bridges().unwrap();
+        let ids = bridges.iter().map(|bridge| bridge.id()).collect::<Vec<_>>();
+        assert_eq!(ids, ["1234:00:01.0"]);
+        assert_eq!(
+            devices[2].root_port().unwrap().unwrap().id(),
+            "1234:00:01.0"
+        );
+        assert!(devices[0].parent_bridge().unwrap().is_none());
+        assert_eq!(devices[2].numa_node().unwrap(), Some(0));
+        assert_eq!(devices[3].numa_node().unwrap(), None);
+    }
+}
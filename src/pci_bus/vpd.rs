@@ -0,0 +1,299 @@
+use super::PciDevice;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+
+// Resource tags. Copied from kernel header.
+// https://git.kernel.org/pub/scm/linux/kernel/git/torvalds/linux.git/tree/include/linux/pci.h
+const PCI_VPD_LRDT: u8 = 0x80;
+const PCI_VPD_LRDT_ID_STRING: u8 = 0x82;
+const PCI_VPD_LRDT_RO_DATA: u8 = 0x90;
+const PCI_VPD_LRDT_RW_DATA: u8 = 0x91;
+const PCI_VPD_STIN_END: u8 = 0x0f;
+
+/// A keyword of the read-only or read-write section of the VPD.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PciVpdField {
+    /// Two character keyword, such as `PN` or `V0`.
+    pub keyword: String,
+    pub data: Vec<u8>,
+}
+
+impl PciVpdField {
+    /// The data as text, without trailing padding.
+    pub fn value(&self) -> String {
+        String::from_utf8_lossy(&self.data)
+            .trim_end_matches(|c: char| c == '\0' || c.is_whitespace())
+            .to_owned()
+    }
+
+    /// Whether the keyword is vendor specific, which is `V0` to `VZ`.
+    pub fn is_vendor_specific(&self) -> bool {
+        self.keyword.starts_with('V')
+    }
+}
+
+/// Vital product data of a PCI device, from its `vpd` file.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PciVpd {
+    /// Identifier string, which is the name of the product.
+    pub product_name: Option<String>,
+    pub read_only: Vec<PciVpdField>,
+    pub read_write: Vec<PciVpdField>,
+    /// Whether the `RV` checksum matches, or `None` if there is no checksum.
+    pub checksum_valid: Option<bool>,
+}
+
+impl PciVpd {
+    /// Parses VPD resources up to the end tag, or the end of `data` if there is none.
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let truncated = |offset: usize| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("VPD is truncated at offset {:#x}", offset),
+            )
+        };
+
+        let mut vpd = PciVpd::default();
+        let mut offset = 0;
+        while let Some(&tag) = data.get(offset) {
+            let (name, start, len) = if tag & PCI_VPD_LRDT != 0 {
+                let len = data
+                    .get(offset + 1..offset + 3)
+                    .ok_or_else(|| truncated(offset))?;
+                (
+                    tag,
+                    offset + 3,
+                    usize::from(u16::from_le_bytes([len[0], len[1]])),
+                )
+            } else {
+                ((tag >> 3) & 0x0f, offset + 1, usize::from(tag & 0x07))
+            };
+
+            if tag & PCI_VPD_LRDT == 0 && name == PCI_VPD_STIN_END {
+                break;
+            }
+
+            let body = data
+                .get(start..start + len)
+                .ok_or_else(|| truncated(offset))?;
+            match name {
+                PCI_VPD_LRDT_ID_STRING => {
+                    vpd.product_name = Some(
+                        String::from_utf8_lossy(body)
+                            .trim_end_matches(|c: char| c == '\0' || c.is_whitespace())
+                            .to_owned(),
+                    );
+                }
+                PCI_VPD_LRDT_RO_DATA => {
+                    vpd.read_only = parse_fields(body, start)?;
+                    if let Some(rv) = vpd.read_only.iter().position(|f| f.keyword == "RV") {
+                        // The checksum is the first byte of `RV`, and the sum of every byte
+                        // up to and including it is zero.
+                        let end = start
+                            + vpd.read_only[..rv]
+                                .iter()
+                                .map(|field| 3 + field.data.len())
+                                .sum::<usize>()
+                            + 3;
+                        vpd.checksum_valid = data.get(..=end).map(|bytes| {
+                            bytes.iter().fold(0u8, |sum, &byte| sum.wrapping_add(byte)) == 0
+                        });
+                    }
+                }
+                PCI_VPD_LRDT_RW_DATA => vpd.read_write = parse_fields(body, start)?,
+                _ => (),
+            }
+
+            offset = start + len;
+        }
+
+        Ok(vpd)
+    }
+
+    /// Finds a keyword in the read-only section, then the read-write section.
+    pub fn get(&self, keyword: &str) -> Option<&PciVpdField> {
+        self.read_only
+            .iter()
+            .chain(self.read_write.iter())
+            .find(|field| field.keyword == keyword)
+    }
+
+    pub fn part_number(&self) -> Option<String> {
+        self.get("PN").map(PciVpdField::value)
+    }
+
+    pub fn serial_number(&self) -> Option<String> {
+        self.get("SN").map(PciVpdField::value)
+    }
+
+    pub fn engineering_change(&self) -> Option<String> {
+        self.get("EC").map(PciVpdField::value)
+    }
+
+    pub fn manufacturer_id(&self) -> Option<String> {
+        self.get("MN").map(PciVpdField::value)
+    }
+
+    /// Vendor specific keywords, `V0` to `VZ`, from both sections.
+    pub fn vendor_specific(&self) -> impl Iterator<Item = &PciVpdField> {
+        self.read_only
+            .iter()
+            .chain(self.read_write.iter())
+            .filter(|field| field.is_vendor_specific())
+    }
+}
+
+/// Parses the keywords of a read-only or read-write section, which starts at `base`.
+fn parse_fields(body: &[u8], base: usize) -> Result<Vec<PciVpdField>> {
+    let mut fields = Vec::new();
+    let mut offset = 0;
+    while offset < body.len() {
+        let header = body.get(offset..offset + 3).ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("VPD keyword is truncated at offset {:#x}", base + offset),
+            )
+        })?;
+
+        let start = offset + 3;
+        let end = start + usize::from(header[2]);
+        let data = body.get(start..end).ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("VPD keyword is truncated at offset {:#x}", base + offset),
+            )
+        })?;
+
+        fields.push(PciVpdField {
+            keyword: String::from_utf8_lossy(&header[..2]).into_owned(),
+            data: data.to_vec(),
+        });
+
+        offset = end;
+    }
+
+    Ok(fields)
+}
+
+impl PciDevice {
+    /// Reads and parses the vital product data of the device. Only root can read it.
+    pub fn vpd(&self) -> Result<PciVpd> {
+        PciVpd::parse(&fs::read(self.path.join("vpd"))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resource(tag: u8, body: &[u8]) -> Vec<u8> {
+        let mut resource = vec![tag];
+        resource.extend_from_slice(&(body.len() as u16).to_le_bytes());
+        resource.extend_from_slice(body);
+        resource
+    }
+
+    fn field(keyword: &str, data: &[u8]) -> Vec<u8> {
+        let mut field = keyword.as_bytes().to_vec();
+        field.push(data.len() as u8);
+        field.extend_from_slice(data);
+        field
+    }
+
+    /// VPD with an identifier string, a read-only section ending in a valid `RV`
+    /// checksum, a read-write section and an end tag.
+    fn sample() -> Vec<u8> {
+        let mut vpd = resource(PCI_VPD_LRDT_ID_STRING, b"Example Dual Port NIC  ");
+
+        let mut read_only = [
+            field("PN", b"X550-T2"),
+            field("EC", b"G12345-001"),
+            field("SN", b"ABC123\0\0"),
+            field("MN", b"8086"),
+            field("V0", b"FW 1.2"),
+        ]
+        .concat();
+        let checksum = vpd.len() + 3 + read_only.len() + 3;
+        read_only.extend_from_slice(&field("RV", &[0; 4]));
+        vpd.extend(resource(PCI_VPD_LRDT_RO_DATA, &read_only));
+        vpd[checksum] = vpd[..checksum]
+            .iter()
+            .fold(0u8, |sum, &byte| sum.wrapping_sub(byte));
+
+        let read_write = [field("V1", b"rw"), field("YA", b"asset tag")].concat();
+        vpd.extend(resource(PCI_VPD_LRDT_RW_DATA, &read_write));
+        vpd.push(PCI_VPD_STIN_END << 3);
+        vpd
+    }
+
+    #[test]
+    fn parse() {
+        let vpd = PciVpd::parse(&sample()).unwrap();
+        assert_eq!(vpd.product_name.as_deref(), Some("Example Dual Port NIC"));
+        assert_eq!(vpd.checksum_valid, Some(true));
+        assert_eq!(vpd.part_number().as_deref(), Some("X550-T2"));
+        assert_eq!(vpd.engineering_change().as_deref(), Some("G12345-001"));
+        assert_eq!(vpd.serial_number().as_deref(), Some("ABC123"));
+        assert_eq!(vpd.manufacturer_id().as_deref(), Some("8086"));
+        assert_eq!(
+            vpd.get("YA").map(PciVpdField::value).as_deref(),
+            Some("asset tag")
+        );
+        assert_eq!(vpd.read_only.len(), 6);
+        assert_eq!(vpd.read_write.len(), 2);
+
+        let vendor = vpd
+            .vendor_specific()
+            .map(|field| (field.keyword.as_str(), field.value()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vendor,
+            [("V0", "FW 1.2".to_owned()), ("V1", "rw".to_owned())]
+        );
+    }
+
+    #[test]
+    fn end_tag() {
+        // Bytes after the end tag are ignored, and the end tag itself is optional.
+        let mut vpd = sample();
+        vpd.extend_from_slice(&[0xff; 16]);
+        assert_eq!(
+            PciVpd::parse(&vpd).unwrap(),
+            PciVpd::parse(&sample()).unwrap()
+        );
+
+        let vpd = resource(PCI_VPD_LRDT_ID_STRING, b"Name");
+        let vpd = PciVpd::parse(&vpd).unwrap();
+        assert_eq!(vpd.product_name.as_deref(), Some("Name"));
+        assert_eq!(vpd.checksum_valid, None);
+
+        assert_eq!(PciVpd::parse(&[]).unwrap(), PciVpd::default());
+    }
+
+    #[test]
+    fn bad_checksum() {
+        let mut vpd = sample();
+        // Change a byte of the part number, which the checksum covers.
+        let pn = vpd.windows(2).position(|bytes| bytes == b"PN").unwrap();
+        vpd[pn + 3] ^= 0x01;
+
+        let vpd = PciVpd::parse(&vpd).unwrap();
+        assert_eq!(vpd.checksum_valid, Some(false));
+        assert_eq!(vpd.part_number().as_deref(), Some("Y550-T2"));
+    }
+
+    #[test]
+    fn truncated() {
+        let vpd = sample();
+        // Cut inside a large resource header, inside a resource body, and inside a keyword.
+        for &len in &[1, 2, 10, vpd.len() - 8] {
+            let err = PciVpd::parse(&vpd[..len]).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidData, "{}", len);
+        }
+
+        // A keyword whose length runs past the end of its section.
+        let vpd = resource(PCI_VPD_LRDT_RO_DATA, &[b'P', b'N', 8, b'X']);
+        let err = PciVpd::parse(&vpd).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+}
@@ -1,4 +1,8 @@
-use std::io;
+use crate::SysClass;
+use std::io::{self, Error, ErrorKind};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
 
 /// Control whether a device uses, or does not use, runtime power management.
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -16,6 +20,193 @@ impl From<RuntimePowerManagement> for &'static str {
     }
 }
 
+impl FromStr for RuntimePowerManagement {
+    type Err = Error;
+    fn from_str(s: &str) -> io::Result<Self> {
+        match s {
+            "auto" => Ok(RuntimePowerManagement::On),
+            "on" => Ok(RuntimePowerManagement::Off),
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("unknown power control: {}", s),
+            )),
+        }
+    }
+}
+
+/// The runtime power state of a device, from `power/runtime_status`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RuntimeStatus {
+    Active,
+    Resuming,
+    Suspended,
+    Suspending,
+    Error,
+    /// Runtime power management is disabled for the device.
+    Unsupported,
+}
+
+impl FromStr for RuntimeStatus {
+    type Err = Error;
+    fn from_str(s: &str) -> io::Result<Self> {
+        Ok(match s {
+            "active" => RuntimeStatus::Active,
+            "resuming" => RuntimeStatus::Resuming,
+            "suspended" => RuntimeStatus::Suspended,
+            "suspending" => RuntimeStatus::Suspending,
+            "error" => RuntimeStatus::Error,
+            "unsupported" => RuntimeStatus::Unsupported,
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("unknown runtime status: {}", s),
+                ))
+            }
+        })
+    }
+}
+
 pub trait RuntimePM {
     fn set_runtime_pm(&self, state: RuntimePowerManagement) -> io::Result<()>;
 }
+
+/// Runtime power management attributes beyond `RuntimePM::set_runtime_pm`, which every
+/// sys object with a `power` directory has.
+pub trait RuntimePMExt: SysClass {
+    /// Directory of the power attributes, relative to the path of the sys object.
+    fn power_dir(&self) -> &'static str {
+        "power"
+    }
+
+    fn runtime_pm(&self) -> io::Result<RuntimePowerManagement> {
+        self.parse_file(Path::new(self.power_dir()).join("control"))
+    }
+
+    fn runtime_status(&self) -> io::Result<RuntimeStatus> {
+        self.parse_file(Path::new(self.power_dir()).join("runtime_status"))
+    }
+
+    /// Total time the device has spent suspended since runtime PM was enabled.
+    fn runtime_suspended_time(&self) -> io::Result<Duration> {
+        self.parse_file(Path::new(self.power_dir()).join("runtime_suspended_time"))
+            .map(Duration::from_millis)
+    }
+
+    /// Total time the device has spent active since runtime PM was enabled.
+    fn runtime_active_time(&self) -> io::Result<Duration> {
+        self.parse_file(Path::new(self.power_dir()).join("runtime_active_time"))
+            .map(Duration::from_millis)
+    }
+
+    /// Idle time before the device is suspended. A negative delay prevents autosuspend.
+    fn autosuspend_delay_ms(&self) -> io::Result<i64> {
+        self.parse_file(Path::new(self.power_dir()).join("autosuspend_delay_ms"))
+    }
+
+    fn set_autosuspend_delay_ms(&self, delay: i64) -> io::Result<()> {
+        self.write_file(
+            Path::new(self.power_dir()).join("autosuspend_delay_ms"),
+            delay.to_string(),
+        )
+    }
+
+    /// Whether the device is allowed to wake the system from sleep, or `None` if the device
+    /// is not capable of waking the system.
+    fn wakeup(&self) -> io::Result<Option<bool>> {
+        let value = self.trim_file(Path::new(self.power_dir()).join("wakeup"))?;
+        match value.as_str() {
+            "enabled" => Ok(Some(true)),
+            "disabled" => Ok(Some(false)),
+            "" => Ok(None),
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("unknown wakeup setting: {}", value),
+            )),
+        }
+    }
+
+    fn set_wakeup(&self, enable: bool) -> io::Result<()> {
+        self.write_file(
+            Path::new(self.power_dir()).join("wakeup"),
+            if enable { "enabled" } else { "disabled" },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use crate::{Block, PciDevice};
+    use std::fs;
+
+    fn fixture(name: &str, power_dir: &str) -> TempDir {
+        let dir = TempDir::new(name);
+        let power = dir.path().join(power_dir);
+        fs::create_dir_all(&power).unwrap();
+        fs::write(power.join("control"), "auto\n").unwrap();
+        fs::write(power.join("runtime_status"), "suspended\n").unwrap();
+        fs::write(power.join("runtime_suspended_time"), "1500\n").unwrap();
+        fs::write(power.join("runtime_active_time"), "250\n").unwrap();
+        fs::write(power.join("autosuspend_delay_ms"), "-1\n").unwrap();
+        fs::write(power.join("wakeup"), "enabled\n").unwrap();
+        dir
+    }
+
+    fn check(device: &impl RuntimePMExt) {
+        assert_eq!(device.runtime_pm().unwrap(), RuntimePowerManagement::On);
+        assert_eq!(device.runtime_status().unwrap(), RuntimeStatus::Suspended);
+        assert_eq!(
+            device.runtime_suspended_time().unwrap(),
+            Duration::from_millis(1500)
+        );
+        assert_eq!(
+            device.runtime_active_time().unwrap(),
+            Duration::from_millis(250)
+        );
+        assert_eq!(device.autosuspend_delay_ms().unwrap(), -1);
+        assert_eq!(device.wakeup().unwrap(), Some(true));
+    }
+
+    #[test]
+    fn attributes() {
+        let dir = fixture("runtime-pm-pci", "power");
+        check(&unsafe { PciDevice::from_path_unchecked(dir.path().to_path_buf()) });
+
+        // Block devices keep their power attributes on the underlying device.
+        let dir = fixture("runtime-pm-block", "device/power");
+        check(&unsafe { Block::from_path_unchecked(dir.path().to_path_buf()) });
+    }
+
+    #[test]
+    fn wakeup() {
+        let dir = fixture("runtime-pm-wakeup", "power");
+        let device = unsafe { PciDevice::from_path_unchecked(dir.path().to_path_buf()) };
+
+        for (value, expected) in &[("disabled\n", Some(false)), ("\n", None)] {
+            fs::write(dir.path().join("power/wakeup"), value).unwrap();
+            assert_eq!(device.wakeup().unwrap(), *expected);
+        }
+
+        fs::write(dir.path().join("power/wakeup"), "maybe\n").unwrap();
+        assert_eq!(device.wakeup().unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn runtime_status() {
+        let statuses = [
+            ("active", RuntimeStatus::Active),
+            ("resuming", RuntimeStatus::Resuming),
+            ("suspended", RuntimeStatus::Suspended),
+            ("suspending", RuntimeStatus::Suspending),
+            ("error", RuntimeStatus::Error),
+            ("unsupported", RuntimeStatus::Unsupported),
+        ];
+        for (s, status) in &statuses {
+            assert_eq!(s.parse::<RuntimeStatus>().unwrap(), *status);
+        }
+
+        let err = "idle".parse::<RuntimeStatus>().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+}
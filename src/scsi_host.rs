@@ -1,4 +1,4 @@
-use crate::SysClass;
+use crate::{RuntimePM, RuntimePMExt, RuntimePowerManagement, SysClass};
 use std::io::{self, Result};
 use std::path::{Path, PathBuf};
 
@@ -67,3 +67,16 @@ impl ScsiHost {
 
     method!(use_blk_mq parse_file u8);
 }
+
+/// Runtime power management of the SCSI host adapter.
+impl RuntimePM for ScsiHost {
+    fn set_runtime_pm(&self, state: RuntimePowerManagement) -> Result<()> {
+        self.write_file("device/power/control", <&'static str>::from(state))
+    }
+}
+
+impl RuntimePMExt for ScsiHost {
+    fn power_dir(&self) -> &'static str {
+        "device/power"
+    }
+}
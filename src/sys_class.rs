@@ -77,6 +77,10 @@ pub trait SysClass: Sized {
     fn class() -> &'static str;
 
     /// Create a sys object from an absolute path without checking path for validity
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that `path` refers to an object of this sys class.
     unsafe fn from_path_unchecked(path: PathBuf) -> Self;
 
     /// Return the path of the sys object
@@ -106,7 +110,7 @@ pub trait SysClass: Sized {
             }
         }
 
-        fs::read_dir(&path)?;
+        fs::read_dir(path)?;
 
         Ok(unsafe { Self::from_path_unchecked(path.to_owned()) })
     }